@@ -1,17 +1,194 @@
+use std::borrow::Cow;
+
+use bincode::Options;
 use serde::{de::DeserializeOwned, Serialize};
 
 use crate::error::SerdeFlowError;
 
-use super::FlowEncoder;
+use super::{FlowEncoder, FlowEncoderInstance};
 
 pub struct Encoder;
 impl FlowEncoder for Encoder {
+    const ID: u8 = 2;
+
     fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, SerdeFlowError> {
-        let bytes = bincode::serialize(value).map_err(|_| SerdeFlowError::ParsingFailed)?;
-        Ok(bytes)
+        bincode::serialize(value).map_err(encode_error::<T>)
+    }
+    fn serialize_into<T: Serialize>(value: &T, buffer: &mut Vec<u8>) -> Result<(), SerdeFlowError> {
+        bincode::serialize_into(buffer, value).map_err(encode_error::<T>)
     }
     fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, SerdeFlowError> {
-        let object: T = bincode::deserialize(bytes).map_err(|_| SerdeFlowError::ParsingFailed)?;
-        Ok(object)
+        bincode::deserialize(bytes).map_err(decode_error::<T>)
+    }
+    fn legacy_payload(bytes: &[u8]) -> Result<(u16, Cow<'_, [u8]>), SerdeFlowError> {
+        Config::default().legacy_payload(bytes)
+    }
+}
+
+fn encode_error<T>(error: bincode::Error) -> SerdeFlowError {
+    SerdeFlowError::Encode {
+        encoder: Encoder::ID,
+        type_name: std::any::type_name::<T>(),
+        source: error,
+    }
+}
+
+fn decode_error<T>(error: bincode::Error) -> SerdeFlowError {
+    SerdeFlowError::Decode {
+        encoder: Encoder::ID,
+        type_name: std::any::type_name::<T>(),
+        source: error,
+    }
+}
+
+impl Encoder {
+    /// Creates a bincode encoder with variable-length or fixed-size integers, the byte order
+    /// and the maximal size of the encoded and decoded payloads in bytes.
+    /// Payloads above the limit fail with `SerdeFlowError::Encode` or `SerdeFlowError::Decode`.
+    ///
+    /// `with_options(false, false, None)` produces the same payloads as [`Encoder`].
+    /// The integer encoding and the byte order are recorded in the header, decoding the payload with other options
+    /// fails with `SerdeFlowError::EncoderOptionsMismatch`, and `decode_auto` picks them from the header.
+    ///
+    /// ```
+    /// use serde::{Deserialize, Serialize};
+    /// use serde_flow::encoder::bincode;
+    /// use serde_flow::flow::Bytes;
+    /// use serde_flow::Flow;
+    ///
+    /// #[derive(Serialize, Deserialize, Flow)]
+    /// #[flow(variant = 1, bytes)]
+    /// struct User {
+    ///     id: u64,
+    /// }
+    ///
+    /// let encoder = bincode::Encoder::with_options(true, true, Some(1024));
+    /// let bytes = User { id: 7 }.encode_with(&encoder).unwrap();
+    /// assert_eq!(User::decode_with(&encoder, &bytes).unwrap().id, 7);
+    /// ```
+    #[must_use]
+    pub const fn with_options(varint: bool, big_endian: bool, limit: Option<u64>) -> Config {
+        Config {
+            varint,
+            big_endian,
+            limit,
+        }
+    }
+}
+
+/// Header flag of the payloads encoded with variable-length integers.
+pub const FLAG_VARINT: u16 = 1 << 8;
+/// Header flag of the payloads encoded in big-endian byte order.
+pub const FLAG_BIG_ENDIAN: u16 = 1 << 9;
+
+/// Bincode encoder with the options chosen at runtime, see [`Encoder::with_options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    varint: bool,
+    big_endian: bool,
+    limit: Option<u64>,
+}
+
+impl Config {
+    /// Creates the config the payload with the header flags was encoded with, without a limit.
+    #[must_use]
+    pub const fn from_flags(flags: u16) -> Self {
+        Encoder::with_options(flags & FLAG_VARINT != 0, flags & FLAG_BIG_ENDIAN != 0, None)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Encoder::with_options(false, false, None)
+    }
+}
+
+/// Builds the bincode options of the config, every combination is a different type.
+macro_rules! with_options {
+    ($config:expr, |$options:ident| $body:expr) => {{
+        let options = bincode::DefaultOptions::new().allow_trailing_bytes();
+        match ($config.varint, $config.big_endian) {
+            (true, true) => with_limit!(
+                $config,
+                options.with_varint_encoding().with_big_endian(),
+                |$options| $body
+            ),
+            (true, false) => with_limit!(
+                $config,
+                options.with_varint_encoding().with_little_endian(),
+                |$options| $body
+            ),
+            (false, true) => with_limit!(
+                $config,
+                options.with_fixint_encoding().with_big_endian(),
+                |$options| $body
+            ),
+            (false, false) => with_limit!(
+                $config,
+                options.with_fixint_encoding().with_little_endian(),
+                |$options| $body
+            ),
+        }
+    }};
+}
+
+macro_rules! with_limit {
+    ($config:expr, $base:expr, |$options:ident| $body:expr) => {
+        match $config.limit {
+            Some(limit) => {
+                let $options = $base.with_limit(limit);
+                $body
+            }
+            None => {
+                let $options = $base.with_no_limit();
+                $body
+            }
+        }
+    };
+}
+
+impl FlowEncoderInstance for Config {
+    fn id(&self) -> u8 {
+        Encoder::ID
+    }
+
+    fn flags(&self) -> u16 {
+        let varint = if self.varint { FLAG_VARINT } else { 0 };
+        let big_endian = if self.big_endian { FLAG_BIG_ENDIAN } else { 0 };
+        varint | big_endian
+    }
+
+    fn serialize_into<T: Serialize>(
+        &self,
+        value: &T,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerdeFlowError> {
+        with_options!(self, |options| options
+            .serialize_into(&mut *buffer, value)
+            .map_err(encode_error::<T>))
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, SerdeFlowError> {
+        // bincode drops the limit when deserializing a slice, a reader keeps it
+        with_options!(self, |options| if self.limit.is_some() {
+            options.deserialize_from(bytes)
+        } else {
+            options.deserialize(bytes)
+        }
+        .map_err(decode_error::<T>))
+    }
+
+    /// The old payload is the object with the variant as its first field, so the fields follow the variant.
+    fn legacy_payload<'a>(&self, bytes: &'a [u8]) -> Result<(u16, Cow<'a, [u8]>), SerdeFlowError> {
+        with_options!(self, |options| {
+            let variant: u16 = options
+                .deserialize(bytes)
+                .map_err(|_| SerdeFlowError::FormatInvalid)?;
+            let size = options
+                .serialized_size(&variant)
+                .map_err(|_| SerdeFlowError::FormatInvalid)?;
+            let size = usize::try_from(size).map_err(|_| SerdeFlowError::FormatInvalid)?;
+            Ok((variant, Cow::Borrowed(&bytes[size..])))
+        })
     }
 }
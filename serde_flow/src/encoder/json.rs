@@ -1,17 +1,88 @@
-use super::FlowEncoder;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use super::{FlowEncoder, FlowEncoderInstance};
 use crate::error::SerdeFlowError;
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::value::RawValue;
 
 pub struct Encoder;
 impl FlowEncoder for Encoder {
+    const ID: u8 = 3;
+
     fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, SerdeFlowError> {
-        let json_string =
-            serde_json::to_string(value).map_err(|_| SerdeFlowError::EncodingFailed)?;
-        println!("Json\n{json_string}");
-        Ok(json_string.as_bytes().to_vec())
+        serde_json::to_vec(value).map_err(encode_error::<T>)
+    }
+    fn serialize_into<T: Serialize>(value: &T, buffer: &mut Vec<u8>) -> Result<(), SerdeFlowError> {
+        serde_json::to_writer(buffer, value).map_err(encode_error::<T>)
     }
     fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, SerdeFlowError> {
-        let object: T = serde_json::from_slice(bytes).map_err(|_| SerdeFlowError::ParsingFailed)?;
-        Ok(object)
+        serde_json::from_slice(bytes).map_err(|error| SerdeFlowError::Decode {
+            encoder: Encoder::ID,
+            type_name: std::any::type_name::<T>(),
+            source: Box::new(error),
+        })
+    }
+    /// The old payload is the object with the `flow_id` field. The field is removed, so the object decodes
+    /// into types denying unknown fields, the other fields are copied without being parsed.
+    fn legacy_payload(bytes: &[u8]) -> Result<(u16, Cow<'_, [u8]>), SerdeFlowError> {
+        let mut object: BTreeMap<String, &RawValue> =
+            serde_json::from_slice(bytes).map_err(|_| SerdeFlowError::FormatInvalid)?;
+        let flow_id = object
+            .remove("flow_id")
+            .ok_or(SerdeFlowError::FormatInvalid)?;
+        let flow_id: u16 =
+            serde_json::from_str(flow_id.get()).map_err(|_| SerdeFlowError::FormatInvalid)?;
+        let payload = serde_json::to_vec(&object).map_err(|_| SerdeFlowError::FormatInvalid)?;
+        Ok((flow_id, Cow::Owned(payload)))
+    }
+}
+
+fn encode_error<T>(error: serde_json::Error) -> SerdeFlowError {
+    SerdeFlowError::Encode {
+        encoder: Encoder::ID,
+        type_name: std::any::type_name::<T>(),
+        source: Box::new(error),
+    }
+}
+
+impl Encoder {
+    /// Creates a json encoder writing indented, human readable payloads.
+    /// The payloads are decoded by [`Encoder`] as well.
+    #[must_use]
+    pub const fn pretty() -> Config {
+        Config { pretty: true }
+    }
+}
+
+/// Json encoder with the options chosen at runtime, see [`Encoder::pretty`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Config {
+    pretty: bool,
+}
+
+impl FlowEncoderInstance for Config {
+    fn id(&self) -> u8 {
+        Encoder::ID
+    }
+
+    fn serialize_into<T: Serialize>(
+        &self,
+        value: &T,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerdeFlowError> {
+        if self.pretty {
+            serde_json::to_writer_pretty(buffer, value).map_err(encode_error::<T>)
+        } else {
+            serde_json::to_writer(buffer, value).map_err(encode_error::<T>)
+        }
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, SerdeFlowError> {
+        Encoder::deserialize(bytes)
+    }
+
+    fn legacy_payload<'a>(&self, bytes: &'a [u8]) -> Result<(u16, Cow<'a, [u8]>), SerdeFlowError> {
+        Encoder::legacy_payload(bytes)
     }
 }
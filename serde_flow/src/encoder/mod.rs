@@ -1,3 +1,6 @@
+use std::borrow::Cow;
+use std::marker::PhantomData;
+
 use crate::error::SerdeFlowError;
 use crc::{Crc, CRC_32_ISCSI};
 
@@ -8,9 +11,134 @@ pub mod json;
 #[cfg(feature = "zerocopy")]
 pub mod zerocopy;
 
-pub trait FlowEncoder {
+/// An encoder selected at compile time, e.g. `bincode::Encoder` or `json::Encoder`.
+///
+/// Custom encoders implement [`FlowEncoder::serialize`] and [`FlowEncoder::deserialize`]. The default
+/// [`FlowEncoder::serialize_into`] serializes into a separate buffer and copies it behind the header, which costs
+/// an extra allocation and a copy of the whole payload on every save and encode, so encoders that can write into
+/// a `Vec<u8>` should override it.
+pub trait FlowEncoder: 'static {
+    /// Identifier of the encoder, written into the header of every encoded payload.
+    /// Identifiers below 128 are reserved for the encoders shipped with serde_flow.
+    const ID: u8;
+
     fn serialize<T: serde::Serialize>(value: &T) -> Result<Vec<u8>, SerdeFlowError>;
+
+    /// Serializes the value at the end of the buffer.
+    /// The default implementation copies the output of [`FlowEncoder::serialize`], encoders should override it
+    /// to write into the buffer directly.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError` if the value can't be serialized.
+    fn serialize_into<T: serde::Serialize>(
+        value: &T,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerdeFlowError> {
+        buffer.extend_from_slice(&Self::serialize(value)?);
+        Ok(())
+    }
     fn deserialize<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Result<T, SerdeFlowError>;
+
+    /// Splits the bytes written by releases before the header into the variant and the payload of the object,
+    /// see [`crate::flow::header::is_legacy`]. The payload is borrowed from the bytes unless the variant
+    /// has to be removed from it. The default implementation doesn't support the old layout.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::FormatInvalid` if the variant can't be read.
+    fn legacy_payload(_bytes: &[u8]) -> Result<(u16, Cow<'_, [u8]>), SerdeFlowError> {
+        Err(SerdeFlowError::FormatInvalid)
+    }
+}
+
+/// An encoder configured at runtime, e.g. bincode with other options or pretty-printed json.
+///
+/// Passed by reference to the `*_with` methods of `File`, `FileAsync` and `Bytes`,
+/// see `bincode::Encoder::with_options` and `json::Encoder::pretty`.
+pub trait FlowEncoderInstance {
+    /// Identifier of the encoder, written into the header of every encoded payload, see [`FlowEncoder::ID`].
+    fn id(&self) -> u8;
+
+    /// Flags describing the options the payloads are encoded with, written into the bits [`ENCODER_FLAGS`]
+    /// of the header. Payloads written with other flags fail to decode with `SerdeFlowError::EncoderOptionsMismatch`,
+    /// the default is for encoders whose options don't change the payload.
+    ///
+    /// [`ENCODER_FLAGS`]: crate::flow::header::ENCODER_FLAGS
+    fn flags(&self) -> u16 {
+        0
+    }
+
+    /// Serializes the value at the end of the buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError` if the value can't be serialized.
+    fn serialize_into<T: serde::Serialize>(
+        &self,
+        value: &T,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerdeFlowError>;
+
+    /// Deserializes the value from the bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError` if the value can't be deserialized.
+    fn deserialize<T: serde::de::DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+    ) -> Result<T, SerdeFlowError>;
+
+    /// Splits the bytes written by releases before the header into the variant and the payload, see [`FlowEncoder::legacy_payload`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::FormatInvalid` if the variant can't be read.
+    fn legacy_payload<'a>(&self, _bytes: &'a [u8]) -> Result<(u16, Cow<'a, [u8]>), SerdeFlowError> {
+        Err(SerdeFlowError::FormatInvalid)
+    }
+}
+
+/// A zero-config [`FlowEncoder`] used as an instance, the methods taking the encoder as a type parameter go through it.
+pub struct StaticEncoder<E>(pub(crate) PhantomData<fn() -> E>);
+
+impl<E> StaticEncoder<E> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<E> Default for StaticEncoder<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: FlowEncoder> FlowEncoderInstance for StaticEncoder<E> {
+    fn id(&self) -> u8 {
+        E::ID
+    }
+
+    fn serialize_into<T: serde::Serialize>(
+        &self,
+        value: &T,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerdeFlowError> {
+        E::serialize_into(value, buffer)
+    }
+
+    fn deserialize<T: serde::de::DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+    ) -> Result<T, SerdeFlowError> {
+        E::deserialize(bytes)
+    }
+
+    fn legacy_payload<'a>(&self, bytes: &'a [u8]) -> Result<(u16, Cow<'a, [u8]>), SerdeFlowError> {
+        E::legacy_payload(bytes)
+    }
 }
 
 pub const CASTAGNOLI: Crc<u32> = Crc::<u32>::new(&CRC_32_ISCSI);
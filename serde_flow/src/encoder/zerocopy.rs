@@ -1,109 +1,413 @@
-use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::Arc;
 
 use crate::error::SerdeFlowError;
-use rkyv::{ser::Serializer, Archive, Deserialize, Serialize};
+use crate::flow::header::HEADER_SIZE;
+use rkyv::ser::serializers::{AlignedSerializer, FallbackScratch, SharedSerializeMap};
+use rkyv::{ser::Serializer, AlignedVec, Archive, Deserialize, Serialize};
 
 pub type DefaultSerializer = rkyv::ser::serializers::AllocSerializer<4096>;
 
+// The header keeps the archive after it aligned in files and in mapped memory.
+// `usize::is_multiple_of` needs Rust 1.87, here and in `is_aligned`.
+#[allow(clippy::manual_is_multiple_of)]
+const _: () = assert!(HEADER_SIZE % AlignedVec::ALIGNMENT == 0);
+
 pub struct Encoder;
 
 impl Encoder {
-    /// Serializes the provided value into a byte vector.
+    /// Identifier of the encoder, written into the header of every encoded payload.
+    pub const ID: u8 = 1;
+
+    /// Serializes the provided value into a buffer aligned for its archive.
     ///
     /// # Errors
     ///
-    /// Returns a `SerdeFlowError::EncodingFailed` if the encoding process fails.
+    /// Returns a `SerdeFlowError::Encode` if the encoding process fails.
     ///
     /// ```
-    pub fn serialize<T>(value: &T) -> Result<Vec<u8>, crate::error::SerdeFlowError>
+    pub fn serialize<T>(value: &T) -> Result<AlignedVec, crate::error::SerdeFlowError>
     where
         T: Archive + Serialize<DefaultSerializer>,
     {
-        let mut serializer = DefaultSerializer::default();
-        let _ = serializer
-            .serialize_value(value)
-            .map_err(|_| SerdeFlowError::EncodingFailed)?;
-        let bytes = serializer.into_serializer().into_inner().into_vec();
+        let mut bytes = AlignedVec::new();
+        Self::serialize_into(value, &mut bytes)?;
         Ok(bytes)
     }
+
+    /// Serializes the provided value at the end of the buffer, reusing its allocation.
+    /// The archive is read from the slice of the buffer starting at its previous length.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::Encode` if the encoding process fails,
+    /// the buffer keeps the bytes written before the failure.
+    pub fn serialize_into<T>(value: &T, buffer: &mut AlignedVec) -> Result<(), SerdeFlowError>
+    where
+        T: Archive + Serialize<DefaultSerializer>,
+    {
+        let mut serializer = DefaultSerializer::new(
+            AlignedSerializer::new(std::mem::take(buffer)),
+            FallbackScratch::default(),
+            SharedSerializeMap::default(),
+        );
+        let result = serializer.serialize_value(value);
+        *buffer = serializer.into_serializer().into_inner();
+        result.map_err(|error| SerdeFlowError::Encode {
+            encoder: Self::ID,
+            type_name: std::any::type_name::<T>(),
+            source: error.to_string().into(),
+        })?;
+        Ok(())
+    }
+}
+
+/// Owned archive of `T`, validated once when the reader is created.
+///
+/// The reader dereferences to `T::Archived`, it's `Send + Sync` if `T::Archived` is
+/// and can then be shared between threads and tasks in an `Arc`.
+/// The archive is read from the buffer it was created from as long as the buffer is aligned,
+/// misaligned buffers are copied into an aligned one.
+///
+/// ```
+/// use serde_flow::encoder::zerocopy;
+///
+/// #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+/// #[archive(check_bytes)]
+/// struct Person {
+///     pub name: String,
+/// }
+///
+/// // serialize
+/// let person = Person { name: "John Doe".to_string() };
+/// let person_bytes: rkyv::AlignedVec = zerocopy::Encoder::serialize(&person).unwrap();
+///
+/// // zerocopy deserialize
+/// let person_reader = zerocopy::Reader::<Person>::new(person_bytes).unwrap();
+/// assert_eq!(person_reader.name, "John Doe");
+/// ```
+///
+/// The reader of an archive which can't be sent to another thread can't be sent either:
+///
+/// ```compile_fail
+/// struct Local;
+///
+/// impl rkyv::Archive for Local {
+///     type Archived = std::rc::Rc<u8>;
+///     type Resolver = ();
+///
+///     unsafe fn resolve(&self, _pos: usize, _resolver: (), _out: *mut Self::Archived) {}
+/// }
+///
+/// fn assert_send<T: Send>() {}
+/// assert_send::<serde_flow::encoder::zerocopy::Reader<Local>>();
+/// ```
+pub struct Reader<T: rkyv::Archive> {
+    bytes: Storage,
+    archive: PhantomData<T::Archived>,
 }
 
-pub struct Reader<'a, T: rkyv::Archive> {
-    bytes: Box<[u8]>,
-    archived: RefCell<Option<&'a rkyv::Archived<T>>>,
+/// The buffer holding the archive of a [`Reader`].
+enum Storage {
+    Aligned(AlignedVec),
+    Boxed(Box<[u8]>),
+    Shared(Arc<[u8]>),
+    #[cfg(feature = "bytes")]
+    Bytes(bytes::Bytes),
+}
+
+impl Deref for Storage {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Storage::Aligned(bytes) => bytes,
+            Storage::Boxed(bytes) => bytes,
+            Storage::Shared(bytes) => bytes,
+            #[cfg(feature = "bytes")]
+            Storage::Bytes(bytes) => bytes,
+        }
+    }
 }
 
-impl<'a, T: rkyv::Archive> Reader<'a, T>
+impl<T> Reader<T>
 where
     T: rkyv::Archive,
     T::Archived: for<'b> rkyv::CheckBytes<rkyv::validation::validators::DefaultValidator<'b>>,
 {
-    #[must_use]
-    pub fn new(bytes: Vec<u8>) -> Self {
-        Self {
-            bytes: bytes.into_boxed_slice(),
-            archived: RefCell::new(None),
+    /// Creates the reader of the archive produced by [`Encoder::serialize`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::Decode` if the bytes aren't a valid archive of `T`.
+    pub fn new(bytes: AlignedVec) -> Result<Self, SerdeFlowError> {
+        Self::validated(Storage::Aligned(bytes))
+    }
+
+    /// Creates the reader of the archive copied from the slice, which may have any alignment.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::Decode` if the bytes aren't a valid archive of `T`.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, SerdeFlowError> {
+        Self::new(aligned(bytes))
+    }
+
+    /// Creates the reader of the archive in the vector, it's copied only if it isn't aligned.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::Decode` if the bytes aren't a valid archive of `T`.
+    pub fn from_vec(bytes: Vec<u8>) -> Result<Self, SerdeFlowError> {
+        // shrinking the vector into a box may move it, so the box is checked
+        let bytes = bytes.into_boxed_slice();
+        if !is_aligned(&bytes) {
+            return Self::from_slice(&bytes);
         }
+        Self::validated(Storage::Boxed(bytes))
     }
 
-    /// Deserializes the archived data into the original type `T`.
+    /// Creates the reader of the shared archive, it's copied only if it isn't aligned.
     ///
     /// # Errors
     ///
-    /// Returns a `SerdeFlowError::ParsingFailed` if deserialization fails due to incorrect format or insufficient variants.
+    /// Returns a `SerdeFlowError::Decode` if the bytes aren't a valid archive of `T`.
+    pub fn from_arc(bytes: Arc<[u8]>) -> Result<Self, SerdeFlowError> {
+        if !is_aligned(&bytes) {
+            return Self::from_slice(&bytes);
+        }
+        Self::validated(Storage::Shared(bytes))
+    }
+
+    /// Creates the reader of the archive in the buffer, it's copied only if it isn't aligned.
+    ///
+    /// # Errors
     ///
-    /// ```
-    pub fn deserialize(&'a self) -> Result<T, SerdeFlowError>
+    /// Returns a `SerdeFlowError::Decode` if the bytes aren't a valid archive of `T`.
+    #[cfg(feature = "bytes")]
+    pub fn from_bytes(bytes: bytes::Bytes) -> Result<Self, SerdeFlowError> {
+        if !is_aligned(&bytes) {
+            return Self::from_slice(&bytes);
+        }
+        Self::validated(Storage::Bytes(bytes))
+    }
+
+    fn validated(bytes: Storage) -> Result<Self, SerdeFlowError> {
+        validate::<T>(&bytes)?;
+        Ok(Self {
+            bytes,
+            archive: PhantomData,
+        })
+    }
+
+    /// Returns the archived form of the data, the same as dereferencing the reader.
+    #[must_use]
+    pub fn archive(&self) -> &T::Archived {
+        self
+    }
+
+    /// Deserializes the archived data into the original type `T`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::ParsingFailed` if the archive can't be deserialized.
+    pub fn deserialize(&self) -> Result<T, SerdeFlowError>
     where
         rkyv::Archived<T>: Deserialize<T, rkyv::Infallible>,
     {
-        let archived = self.archive()?;
-        archived
+        (**self)
             .deserialize(&mut rkyv::Infallible)
             .map_err(|_| SerdeFlowError::ParsingFailed)
     }
+}
 
-    /// Archives the original data into its archived form.
+impl<T: rkyv::Archive> Deref for Reader<T> {
+    type Target = T::Archived;
+
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the archive was validated when the reader was created and the storage is never modified
+        unsafe { rkyv::archived_root::<T>(&self.bytes) }
+    }
+}
+
+/// Archive of `T` borrowed from a buffer owned by the caller, validated once when the view is created.
+///
+/// Unlike [`Reader`], the view never copies the archive, so the buffer must be aligned to [`AlignedVec::ALIGNMENT`].
+///
+/// ```
+/// use serde_flow::encoder::zerocopy;
+///
+/// #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+/// #[archive(check_bytes)]
+/// struct Person {
+///     pub name: String,
+/// }
+///
+/// let person = Person { name: "John Doe".to_string() };
+/// let person_bytes = zerocopy::Encoder::serialize(&person).unwrap();
+///
+/// let person_view = zerocopy::ArchivedView::<Person>::new(&person_bytes).unwrap();
+/// assert_eq!(person_view.name, "John Doe");
+/// ```
+pub struct ArchivedView<'a, T: rkyv::Archive> {
+    archive: &'a T::Archived,
+}
+
+impl<'a, T> ArchivedView<'a, T>
+where
+    T: rkyv::Archive,
+    T::Archived: for<'b> rkyv::CheckBytes<rkyv::validation::validators::DefaultValidator<'b>>,
+{
+    /// Creates the view of the archive produced by [`Encoder::serialize`].
     ///
     /// # Errors
     ///
-    /// Returns a `SerdeFlowError::ParsingFailed` if parsing fails due to incorrect format.
+    /// Returns a `SerdeFlowError::Decode` if the bytes aren't a valid archive of `T` or aren't aligned.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, SerdeFlowError> {
+        validate::<T>(bytes)?;
+        // SAFETY: the archive was validated above
+        let archive = unsafe { rkyv::archived_root::<T>(bytes) };
+        Ok(Self { archive })
+    }
+
+    /// Returns the archived form of the data, borrowed for the lifetime of the buffer.
+    #[must_use]
+    pub fn archive(&self) -> &'a T::Archived {
+        self.archive
+    }
+
+    /// Deserializes the archived data into the original type `T`.
     ///
-    /// # Examples
+    /// # Errors
     ///
-    /// ```
-    /// use serde_flow::error::SerdeFlowError;
-    /// use std::result::Result;
-    /// use serde_flow::encoder::zerocopy;
+    /// Returns a `SerdeFlowError::ParsingFailed` if the archive can't be deserialized.
+    pub fn deserialize(&self) -> Result<T, SerdeFlowError>
+    where
+        rkyv::Archived<T>: Deserialize<T, rkyv::Infallible>,
+    {
+        self.archive
+            .deserialize(&mut rkyv::Infallible)
+            .map_err(|_| SerdeFlowError::ParsingFailed)
+    }
+}
+
+impl<T: rkyv::Archive> Clone for ArchivedView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: rkyv::Archive> Copy for ArchivedView<'_, T> {}
+
+impl<T: rkyv::Archive> Deref for ArchivedView<'_, T> {
+    type Target = T::Archived;
+
+    fn deref(&self) -> &Self::Target {
+        self.archive
+    }
+}
+
+/// Copies the bytes into a buffer aligned for archives.
+fn aligned(bytes: &[u8]) -> AlignedVec {
+    let mut aligned = AlignedVec::with_capacity(bytes.len());
+    aligned.extend_from_slice(bytes);
+    aligned
+}
+
+#[allow(clippy::manual_is_multiple_of)]
+fn is_aligned(bytes: &[u8]) -> bool {
+    bytes.as_ptr() as usize % AlignedVec::ALIGNMENT == 0
+}
+
+/// Checks that the bytes are a valid archive of `T`.
+fn validate<T>(bytes: &[u8]) -> Result<(), SerdeFlowError>
+where
+    T: rkyv::Archive,
+    T::Archived: for<'b> rkyv::CheckBytes<rkyv::validation::validators::DefaultValidator<'b>>,
+{
+    rkyv::check_archived_root::<T>(bytes).map_err(|error| SerdeFlowError::Decode {
+        encoder: Encoder::ID,
+        type_name: std::any::type_name::<T>(),
+        source: error.to_string().into(),
+    })?;
+    Ok(())
+}
+
+/// Archive of `T` in a memory-mapped file, read without copying the file into memory.
+///
+/// The archive is validated once when the reader is created and dereferences to `T::Archived`,
+/// so the reader is `Send` and `Sync` only if `T::Archived` is.
+/// The file must not be modified by other processes while it's mapped, see [`memmap2::Mmap`].
+#[cfg(feature = "mmap")]
+pub struct MmapReader<T: rkyv::Archive> {
+    mmap: memmap2::Mmap,
+    archive: PhantomData<T::Archived>,
+}
+
+#[cfg(feature = "mmap")]
+impl<T> MmapReader<T>
+where
+    T: rkyv::Archive,
+    T::Archived: for<'b> rkyv::CheckBytes<rkyv::validation::validators::DefaultValidator<'b>>,
+{
+    /// Creates the reader of the mapped file holding the header and the archive of the current variant of `T`.
     ///
-    /// #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
-    /// #[archive(check_bytes)]
-    /// struct Person {
-    ///     pub name: String,
-    /// }
+    /// # Errors
     ///
-    /// // serialize
-    /// let person = Person { name: "John Doe".to_string() };
-    /// let person_bytes: Vec<u8> = zerocopy::Encoder::serialize(&person).unwrap();
+    /// Returns a `SerdeFlowError::EncoderMismatch` if the payload wasn't produced by [`Encoder`],
+    /// `SerdeFlowError::VariantMismatch` if it holds another variant, `SerdeFlowError::ChecksumMismatch`
+    /// if it was corrupted and `SerdeFlowError::Decode` if it isn't an archive of `T`.
+    pub fn new(mmap: memmap2::Mmap) -> Result<Self, SerdeFlowError>
+    where
+        T: crate::flow::FlowInfo,
+    {
+        let (header, payload) = crate::flow::header::Header::split(&mmap)?;
+        header.expect_encoder(Encoder::ID)?;
+        if header.variant != T::VARIANT {
+            return Err(SerdeFlowError::VariantMismatch {
+                type_name: std::any::type_name::<T>(),
+                expected: T::VARIANT,
+                found: header.variant,
+            });
+        }
+        header.verify(payload)?;
+        validate::<T>(payload)?;
+        Ok(Self {
+            mmap,
+            archive: PhantomData,
+        })
+    }
+
+    /// Deserializes the archived data into the original type `T`.
     ///
-    /// // zerocopy deserialize
-    /// let person_reader = zerocopy::Reader::<Person>::new(person_bytes);
-    /// let archive = person_reader.archive().unwrap();
-    /// assert_eq!(archive.name, "John Doe");
+    /// # Errors
     ///
-    /// ```
-    pub fn archive(&'a self) -> Result<&'a T::Archived, SerdeFlowError> {
-        let borrow = self.archived.borrow();
-        if borrow.is_some() {
-            return borrow.ok_or(SerdeFlowError::Undefined);
-        }
-        drop(borrow);
+    /// Returns a `SerdeFlowError::ParsingFailed` if the archive can't be deserialized.
+    pub fn deserialize(&self) -> Result<T, SerdeFlowError>
+    where
+        rkyv::Archived<T>: Deserialize<T, rkyv::Infallible>,
+    {
+        (**self)
+            .deserialize(&mut rkyv::Infallible)
+            .map_err(|_| SerdeFlowError::ParsingFailed)
+    }
+}
 
-        let archive: &'a T::Archived = rkyv::check_archived_root::<T>(&self.bytes)
-            .map_err(|_| SerdeFlowError::ParsingFailed)?;
-        self.archived.replace(Some(archive));
+#[cfg(feature = "mmap")]
+impl<T: rkyv::Archive> Deref for MmapReader<T> {
+    type Target = T::Archived;
 
-        let borrow = self.archived.borrow();
-        borrow.ok_or(SerdeFlowError::Undefined)
+    fn deref(&self) -> &Self::Target {
+        // SAFETY: the archive was validated by `MmapReader::new` and the mapping is read-only
+        unsafe { rkyv::archived_root::<T>(&self.mmap[HEADER_SIZE..]) }
     }
 }
+
+/// Maps the file into memory for reading.
+#[cfg(feature = "mmap")]
+pub(crate) fn map(path: &std::path::Path) -> std::io::Result<memmap2::Mmap> {
+    let file = std::fs::File::open(path)?;
+    // SAFETY: the files written by serde_flow are replaced by renaming, never modified in place
+    unsafe { memmap2::Mmap::map(&file) }
+}
@@ -1,10 +1,28 @@
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum SerdeFlowError {
-    /// Indicates that a variant for the object was not found.
-    #[error("Variant not found for the object")]
-    VariantNotFound,
+    /// Indicates that the variant isn't listed in the `#[variants(..)]` of the type, directly or through a chain.
+    #[error("Variant {variant} is unknown to {type_name}")]
+    VariantUnknown {
+        type_name: &'static str,
+        variant: u16,
+    },
+    /// Indicates that the variant was written by a newer release of the type than the one in this binary.
+    #[error("Variant {variant} is newer than the variant {latest} of {type_name}")]
+    VariantUnsupported {
+        type_name: &'static str,
+        variant: u16,
+        latest: u16,
+    },
+    /// Indicates that the bytes hold another variant of the type than the one expected.
+    #[error("Variant mismatch: expected variant {expected} of {type_name}, found {found}")]
+    VariantMismatch {
+        type_name: &'static str,
+        expected: u16,
+        found: u16,
+    },
     /// Indicates that a file for the object was not found.
     #[error("File not found the object")]
     FileNotFound,
@@ -14,15 +32,101 @@ pub enum SerdeFlowError {
     /// Indicates that parsing of the object failed due to incorrect format or insufficient variants.
     #[error("Parsing failed due to incorrect format or insufficient variants")]
     ParsingFailed,
+    /// Indicates that the encoder failed to encode the object.
+    #[error("Encoding {type_name} with encoder {encoder} failed")]
+    Encode {
+        encoder: u8,
+        type_name: &'static str,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// Indicates that the encoder failed to decode the payload into the object.
+    #[error("Decoding {type_name} with encoder {encoder} failed")]
+    Decode {
+        encoder: u8,
+        type_name: &'static str,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
     /// Indicates that the format of the object is invalid.
     #[error("Invalid data format")]
     FormatInvalid,
+    /// Indicates that the header was written by a newer release of the library.
+    #[error("Unsupported header version {0}")]
+    HeaderVersionUnsupported(u8),
+    /// Indicates that the payload was produced by a different encoder.
+    #[error("Encoder mismatch: expected encoder {expected}, found {found}")]
+    EncoderMismatch { expected: u8, found: u8 },
+    /// Indicates that the payload was produced by the same encoder with other options, e.g. bincode with varint integers.
+    #[error("Encoder options mismatch: encoder {encoder} expected flags {expected:#06x}, found {found:#06x}")]
+    EncoderOptionsMismatch {
+        encoder: u8,
+        expected: u16,
+        found: u16,
+    },
+    /// Indicates that the payload was produced by an encoder which can't be detected.
+    #[error("Unsupported encoder {0}")]
+    EncoderUnsupported(u8),
+    /// Indicates that the stored checksum doesn't match the checksum of the payload.
+    #[error("Checksum mismatch: expected {expected:#010x}, found {found:#010x}")]
+    ChecksumMismatch { expected: u32, found: u32 },
+    /// Indicates that there is no backup to restore the file from.
+    #[error("Backup not found for the file")]
+    BackupNotFound,
+    /// Indicates that the `TryFrom` conversion between two variants failed.
+    #[error("Migration from variant {from_variant} to variant {to_variant} failed")]
+    MigrationFailed {
+        from_variant: u16,
+        to_variant: u16,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
     /// Failed to write bytes on the disk
     #[error("Failed to write bytes on the disk")]
     FailedToWrite,
+    /// Indicates that the operation on the file failed, see [`SerdeFlowError::at_path`].
+    #[error("Operation on file {} failed", path.display())]
+    File {
+        path: PathBuf,
+        source: Box<SerdeFlowError>,
+    },
     /// Indicates an undefined error.
     #[error("An undefined error occurred")]
     Undefined,
     #[error(transparent)]
     IOError(#[from] std::io::Error),
 }
+
+impl SerdeFlowError {
+    /// Returns the error of the variant the type `type_name` with the variant `latest` can't be decoded from,
+    /// `VariantUnsupported` if the variant is newer than `latest` and `VariantUnknown` otherwise.
+    #[must_use]
+    pub fn variant(type_name: &'static str, variant: u16, latest: u16) -> Self {
+        if variant > latest {
+            return SerdeFlowError::VariantUnsupported {
+                type_name,
+                variant,
+                latest,
+            };
+        }
+        SerdeFlowError::VariantUnknown { type_name, variant }
+    }
+
+    /// Adds the path of the file to the error, the errors already holding a path are returned as they are.
+    #[must_use]
+    pub fn at_path(self, path: &Path) -> Self {
+        match self {
+            SerdeFlowError::File { .. } => self,
+            error => SerdeFlowError::File {
+                path: path.to_path_buf(),
+                source: Box::new(error),
+            },
+        }
+    }
+
+    /// Returns the error without the path of the file, e.g. to match on it.
+    #[must_use]
+    pub fn without_path(&self) -> &SerdeFlowError {
+        match self {
+            SerdeFlowError::File { source, .. } => source,
+            error => error,
+        }
+    }
+}
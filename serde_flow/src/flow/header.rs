@@ -0,0 +1,252 @@
+//! Header written in front of every payload produced by `File` and `Bytes` implementations.
+//!
+//! The header has a fixed size of [`HEADER_SIZE`] bytes, all integers are little-endian:
+//!
+//! | Offset | Size | Field                                   |
+//! |--------|------|-----------------------------------------|
+//! | 0      | 4    | magic bytes `SFLW`                      |
+//! | 4      | 1    | header version ([`HEADER_VERSION`])     |
+//! | 5      | 1    | encoder id (see `FlowEncoder::ID`)      |
+//! | 6      | 2    | flags, see [`ENCODER_FLAGS`]            |
+//! | 8      | 2    | variant of the stored entity            |
+//! | 10     | 2    | reserved, always zero                   |
+//! | 12     | 4    | CRC-32C checksum                        |
+//!
+//! The payload produced by the encoder starts right after the header. The size of the header is a multiple
+//! of the alignment of zerocopy archives, so the archive stays aligned in memory-mapped files.
+//! The checksum covers the first 12 bytes of the header and the payload, so a corrupted encoder, flags or variant
+//! is detected as well as a corrupted payload. Version 1 headers always contain it, [`FLAG_CHECKSUM`] is always set.
+//!
+//! Releases before the header wrote the variant as the first field of the payload, see [`is_legacy`].
+//! Such bytes are still decoded, and written with the header the next time they're saved.
+//!
+//! ```
+//! use serde_flow::flow::header::{Header, HEADER_SIZE};
+//!
+//! let bytes = Header::new(2, 7).with_payload(b"payload");
+//! let (header, payload) = Header::split(&bytes).unwrap();
+//!
+//! assert_eq!(bytes.len(), HEADER_SIZE + payload.len());
+//! assert_eq!(header.encoder, 2);
+//! assert_eq!(header.variant, 7);
+//! assert_eq!(payload, b"payload");
+//! assert!(header.verify(payload).is_ok());
+//! ```
+use super::FlowResult;
+use crate::encoder::CASTAGNOLI;
+use crate::error::SerdeFlowError;
+
+/// Magic bytes identifying data written by serde_flow.
+pub const MAGIC: [u8; 4] = *b"SFLW";
+/// Version of the header layout written by this release.
+pub const HEADER_VERSION: u8 = 1;
+/// Size of the header in bytes.
+pub const HEADER_SIZE: usize = 16;
+/// Flag indicating that the header contains the checksum, always set in version 1 headers.
+pub const FLAG_CHECKSUM: u16 = 1;
+/// Bits of the flags describing the options of the encoder, see `FlowEncoderInstance::flags`.
+pub const ENCODER_FLAGS: u16 = 0xFF00;
+
+/// Returns true if the bytes don't start with the magic bytes, i.e. they were written by a release before the header.
+///
+/// The serde payloads of those releases are the object with the variant as its first field `flow_id`,
+/// see `FlowEncoder::legacy_payload`, and the zerocopy ones are the little-endian variant followed by the archive.
+#[must_use]
+pub fn is_legacy(bytes: &[u8]) -> bool {
+    !bytes.starts_with(&MAGIC)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    /// Version of the header layout.
+    pub version: u8,
+    /// Identifier of the encoder that produced the payload.
+    pub encoder: u8,
+    /// Flags describing the payload.
+    pub flags: u16,
+    /// Variant of the stored entity.
+    pub variant: u16,
+    /// CRC-32C checksum of the first 12 bytes of the header and the payload.
+    pub checksum: u32,
+}
+
+impl Header {
+    #[must_use]
+    pub fn new(encoder: u8, variant: u16) -> Self {
+        Self {
+            version: HEADER_VERSION,
+            encoder,
+            flags: 0,
+            variant,
+            checksum: 0,
+        }
+    }
+
+    /// Returns the header with the flags, e.g. the options of the encoder.
+    #[must_use]
+    pub const fn with_flags(self, flags: u16) -> Self {
+        Self { flags, ..self }
+    }
+
+    /// Returns the flags describing the options of the encoder, see [`ENCODER_FLAGS`].
+    #[must_use]
+    pub const fn encoder_flags(&self) -> u16 {
+        self.flags & ENCODER_FLAGS
+    }
+
+    /// Serializes the header into its binary representation.
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0u8; HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&MAGIC);
+        bytes[4] = self.version;
+        bytes[5] = self.encoder;
+        bytes[6..8].copy_from_slice(&self.flags.to_le_bytes());
+        bytes[8..10].copy_from_slice(&self.variant.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.checksum.to_le_bytes());
+        bytes
+    }
+
+    /// Creates a buffer containing the header followed by the payload, the checksum is stored in the header.
+    #[must_use]
+    pub fn with_payload(&self, payload: &[u8]) -> Vec<u8> {
+        let header = self.checksummed(payload);
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + payload.len());
+        bytes.extend_from_slice(&header.to_bytes());
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    /// Creates a buffer containing the header followed by the payload written by `write_payload`.
+    /// The payload is written directly after the header, so it's never copied.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `write_payload`.
+    pub fn with_payload_writer<F>(&self, write_payload: F) -> FlowResult<Vec<u8>>
+    where
+        F: FnOnce(&mut Vec<u8>) -> FlowResult<()>,
+    {
+        let mut bytes = vec![0; HEADER_SIZE];
+        write_payload(&mut bytes)?;
+        self.write_to(&mut bytes);
+        Ok(bytes)
+    }
+
+    /// Writes the header over the first [`HEADER_SIZE`] bytes of the buffer,
+    /// the checksum of the header and the payload following it is stored in the header.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is shorter than [`HEADER_SIZE`].
+    pub fn write_to(&self, bytes: &mut [u8]) {
+        let header = self.checksummed(&bytes[HEADER_SIZE..]);
+        bytes[..HEADER_SIZE].copy_from_slice(&header.to_bytes());
+    }
+
+    /// Returns a copy of the header with the checksum flag set and the checksum of the header and the payload.
+    fn checksummed(&self, payload: &[u8]) -> Self {
+        let mut header = Self {
+            flags: self.flags | FLAG_CHECKSUM,
+            ..*self
+        };
+        header.checksum = header.checksum(payload);
+        header
+    }
+
+    /// Computes the CRC-32C checksum of the first 12 bytes of the header followed by the payload.
+    fn checksum(&self, payload: &[u8]) -> u32 {
+        let mut digest = CASTAGNOLI.digest();
+        digest.update(&self.to_bytes()[..12]);
+        digest.update(payload);
+        digest.finalize()
+    }
+
+    /// Parses the header from the beginning of the bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::FormatInvalid` if the bytes are too short, don't start with the magic bytes
+    /// or the reserved bytes aren't zero, and `SerdeFlowError::HeaderVersionUnsupported` if the header was written
+    /// by a newer release.
+    pub fn parse(bytes: &[u8]) -> FlowResult<Self> {
+        if bytes.len() < HEADER_SIZE || bytes[0..4] != MAGIC {
+            return Err(SerdeFlowError::FormatInvalid);
+        }
+
+        let version = bytes[4];
+        if version != HEADER_VERSION {
+            return Err(SerdeFlowError::HeaderVersionUnsupported(version));
+        }
+
+        if bytes[10..12] != [0, 0] {
+            return Err(SerdeFlowError::FormatInvalid);
+        }
+
+        Ok(Self {
+            version,
+            encoder: bytes[5],
+            flags: u16::from_le_bytes([bytes[6], bytes[7]]),
+            variant: u16::from_le_bytes([bytes[8], bytes[9]]),
+            checksum: u32::from_le_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]),
+        })
+    }
+
+    /// Parses the header and returns it together with the payload that follows it.
+    ///
+    /// # Errors
+    ///
+    /// See [`Header::parse`].
+    pub fn split(bytes: &[u8]) -> FlowResult<(Self, &[u8])> {
+        let header = Self::parse(bytes)?;
+        Ok((header, &bytes[HEADER_SIZE..]))
+    }
+
+    /// Checks that the payload was produced by the expected encoder.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::EncoderMismatch` if the encoder ids are different.
+    pub fn expect_encoder(&self, encoder: u8) -> FlowResult<()> {
+        if self.encoder != encoder {
+            return Err(SerdeFlowError::EncoderMismatch {
+                expected: encoder,
+                found: self.encoder,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that the payload was produced with the options of the encoder described by `flags`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::EncoderOptionsMismatch` if the encoder flags are different.
+    pub fn expect_encoder_flags(&self, flags: u16) -> FlowResult<()> {
+        if self.encoder_flags() != flags {
+            return Err(SerdeFlowError::EncoderOptionsMismatch {
+                encoder: self.encoder,
+                expected: flags,
+                found: self.encoder_flags(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Verifies the checksum of the header and the payload.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::ChecksumMismatch` if the header or the payload was corrupted or truncated,
+    /// including a header without [`FLAG_CHECKSUM`].
+    pub fn verify(&self, payload: &[u8]) -> FlowResult<()> {
+        let checksum = self.checksum(payload);
+        if checksum != self.checksum {
+            return Err(SerdeFlowError::ChecksumMismatch {
+                expected: self.checksum,
+                found: checksum,
+            });
+        }
+        Ok(())
+    }
+}
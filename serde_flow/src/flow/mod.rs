@@ -1,42 +1,597 @@
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Serialize};
 
-use crate::encoder::FlowEncoder;
+use crate::encoder::{FlowEncoder, FlowEncoderInstance, StaticEncoder};
 use crate::error::SerdeFlowError;
+use std::marker::PhantomData;
 use std::path::Path;
 
+pub mod header;
 #[cfg(feature = "zerocopy")]
 pub mod zerocopy;
 
 pub type FlowResult<T> = std::result::Result<T, SerdeFlowError>;
 pub type AsyncResult<'a, T> = futures_util::future::BoxFuture<'a, FlowResult<T>>;
 
+/// Describes the variant of a type and the variants it can be migrated from, implemented by `#[derive(Flow)]`.
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use serde_flow::flow::Encoding;
+/// use serde_flow::{Flow, FlowInfo};
+///
+/// #[derive(Serialize, Deserialize, Flow)]
+/// #[flow(variant = 1)]
+/// struct UserV1 {
+///     name: String,
+/// }
+///
+/// #[derive(Serialize, Deserialize, Flow)]
+/// #[flow(variant = 2)]
+/// #[variants(UserV1)]
+/// struct User {
+///     first_name: String,
+/// }
+/// # impl From<UserV1> for User {
+/// #     fn from(value: UserV1) -> Self {
+/// #         User { first_name: value.name }
+/// #     }
+/// # }
+///
+/// assert_eq!(User::VARIANT, 2);
+/// assert_eq!(User::ACCEPTED_VARIANTS, &[2, 1]);
+/// assert!(User::TYPE_NAME.ends_with("::User"));
+/// assert_eq!(User::ENCODING, Encoding::Serde);
+/// ```
+pub trait FlowInfo {
+    /// The variant written into the header of the encoded type.
+    const VARIANT: u16;
+
+    /// The variants the type can be decoded from: its own variant followed by the variants listed
+    /// in `#[variants(..)]` and, transitively, the variants they can be decoded from.
+    const ACCEPTED_VARIANTS: &'static [u16] = Self::ACCEPTED_VARIANT_SET.as_slice();
+
+    /// The path of the type, e.g. `my_crate::models::User`.
+    const TYPE_NAME: &'static str;
+
+    /// The encoding family the type is stored with.
+    const ENCODING: Encoding;
+
+    /// Backs [`FlowInfo::ACCEPTED_VARIANTS`], the slice can't be built in a constant without a named set.
+    #[doc(hidden)]
+    const ACCEPTED_VARIANT_SET: VariantSet;
+}
+
+/// The family of encoders a type is stored with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Any `FlowEncoder` over serde: bincode, json or a custom one.
+    Serde,
+    /// rkyv archives read without deserialization.
+    Zerocopy,
+}
+
+/// Maximal number of variants a type can be decoded from.
+pub const MAX_ACCEPTED_VARIANTS: usize = 64;
+
+/// A set of variants built in constant context, used to compute [`FlowInfo::ACCEPTED_VARIANTS`].
+#[derive(Debug, Clone, Copy)]
+pub struct VariantSet {
+    variants: [u16; MAX_ACCEPTED_VARIANTS],
+    len: usize,
+}
+
+impl VariantSet {
+    /// Creates a set containing only the variant.
+    #[must_use]
+    pub const fn new(variant: u16) -> Self {
+        let mut variants = [0; MAX_ACCEPTED_VARIANTS];
+        variants[0] = variant;
+        Self { variants, len: 1 }
+    }
+
+    /// Adds the variants which aren't in the set yet, keeping their order.
+    ///
+    /// # Panics
+    ///
+    /// Panics, at compile time, if the set exceeds [`MAX_ACCEPTED_VARIANTS`].
+    #[must_use]
+    pub const fn with(mut self, variants: &[u16]) -> Self {
+        let mut index = 0;
+        while index < variants.len() {
+            if !self.contains(variants[index]) {
+                assert!(
+                    self.len < MAX_ACCEPTED_VARIANTS,
+                    "a type can't be decoded from more than 64 variants"
+                );
+                self.variants[self.len] = variants[index];
+                self.len += 1;
+            }
+            index += 1;
+        }
+        self
+    }
+
+    /// Returns true if the set contains the variant.
+    #[must_use]
+    pub const fn contains(&self, variant: u16) -> bool {
+        let mut index = 0;
+        while index < self.len {
+            if self.variants[index] == variant {
+                return true;
+            }
+            index += 1;
+        }
+        false
+    }
+
+    /// Returns the variants of the set in the order they were added.
+    #[must_use]
+    pub const fn as_slice(&'static self) -> &'static [u16] {
+        self.variants.split_at(self.len).0
+    }
+}
+
+/// Checks, at compile time, that the variants `A` and `B` of a generic type have different ids.
+///
+/// `#[derive(Flow)]` checks the variants of other types with constant assertions next to the type. The ids of the
+/// variants of generic types depend on the type parameters, so they're checked by the `VARIANT` constant of the type,
+/// and the conflicts are reported when the type is built with concrete parameters.
+#[doc(hidden)]
+pub struct VariantIds<A, B>(PhantomData<(A, B)>);
+
+impl<A: FlowInfo, B: FlowInfo> VariantIds<A, B> {
+    pub const DISTINCT: () = assert!(
+        A::VARIANT != B::VARIANT,
+        "variants listed in `#[variants(..)]` must have different ids"
+    );
+}
+
+/// Checks, at compile time, that the variant `A` of a generic type doesn't have the id of the type, see [`VariantIds`].
+#[doc(hidden)]
+pub struct VariantId<A, const CURRENT: u16>(PhantomData<A>);
+
+impl<A: FlowInfo, const CURRENT: u16> VariantId<A, CURRENT> {
+    pub const DISTINCT: () = assert!(
+        A::VARIANT != CURRENT,
+        "variants listed in `#[variants(..)]` must have ids other than the id of the type"
+    );
+}
+
 pub trait File<T: Serialize + DeserializeOwned> {
-    fn load_from_path<E: FlowEncoder>(path: &Path) -> FlowResult<T>;
-    fn save_to_path<E: FlowEncoder>(&self, path: &Path) -> FlowResult<()>;
+    fn load_from_path<E: FlowEncoder>(path: &Path) -> FlowResult<T> {
+        Self::load_from_path_with(&StaticEncoder::<E>(PhantomData), path)
+    }
+    fn save_to_path<E: FlowEncoder>(&self, path: &Path) -> FlowResult<()> {
+        self.save_to_path_with(&StaticEncoder::<E>(PhantomData), path)
+    }
+
+    /// Loads the object with the configured encoder, e.g. `bincode::Encoder::with_options(..)`.
+    fn load_from_path_with<C: FlowEncoderInstance>(encoder: &C, path: &Path) -> FlowResult<T>;
+    /// Saves the object with the configured encoder, e.g. `json::Encoder::pretty()`.
+    fn save_to_path_with<C: FlowEncoderInstance>(&self, encoder: &C, path: &Path)
+        -> FlowResult<()>;
+
+    /// Loads the object with the encoder recorded in the header of the file, see [`decode_auto`].
+    fn load_from_path_auto(path: &Path) -> FlowResult<T>
+    where
+        T: Migrate,
+    {
+        let span = crate::trace::file_span(
+            crate::trace::Operation::Load,
+            std::any::type_name::<T>(),
+            path,
+        );
+        span.in_scope(|| {
+            if !path.exists() {
+                return Err(SerdeFlowError::FileNotFound);
+            }
+            let bytes = std::fs::read(path)?;
+            decode_auto(&bytes)
+        })
+        .map_err(|error| error.at_path(path))
+    }
 }
 
 pub trait FileMigrate<T: Serialize + DeserializeOwned + File<T>> {
     fn load_and_migrate<E: FlowEncoder>(path: &Path) -> FlowResult<T>;
     fn migrate<E: FlowEncoder>(path: &Path) -> FlowResult<()>;
+
+    /// Loads the file with the encoder `S`, migrates it to the current variant and saves it atomically with the encoder `D`.
+    /// With the `file(backup)` option the original file is kept as a backup.
+    /// A file already stored with the encoder `D` and the current variant is left untouched.
+    fn migrate_encoding<S: FlowEncoder, D: FlowEncoder>(path: &Path) -> FlowResult<()>;
+
+    /// Restores the file from the most recent backup created by a migration with the `file(backup)` option.
+    /// Returns the variant of the restored file.
+    fn rollback(path: &Path) -> FlowResult<u16> {
+        crate::fs::rollback(path)
+    }
 }
 
 pub trait FileAsync<T> {
-    fn load_from_path_async<E: FlowEncoder>(path: &Path) -> AsyncResult<T>;
-    fn save_to_path_async<'a, E: FlowEncoder>(&'a self, path: &'a Path) -> AsyncResult<()>;
+    fn load_from_path_async<E: FlowEncoder>(path: &Path) -> AsyncResult<'_, T> {
+        Self::load_from_path_async_with(&StaticEncoder::<E>(PhantomData), path)
+    }
+    fn save_to_path_async<'a, E: FlowEncoder>(&'a self, path: &'a Path) -> AsyncResult<'a, ()> {
+        self.save_to_path_async_with(&StaticEncoder::<E>(PhantomData), path)
+    }
+
+    /// Loads the object with the configured encoder, e.g. `bincode::Encoder::with_options(..)`.
+    fn load_from_path_async_with<'a, C: FlowEncoderInstance + Sync>(
+        encoder: &'a C,
+        path: &'a Path,
+    ) -> AsyncResult<'a, T>;
+    /// Saves the object with the configured encoder, e.g. `json::Encoder::pretty()`.
+    fn save_to_path_async_with<'a, C: FlowEncoderInstance + Sync>(
+        &'a self,
+        encoder: &'a C,
+        path: &'a Path,
+    ) -> AsyncResult<'a, ()>;
+
+    /// Loads the object with the encoder recorded in the header of the file, see [`decode_auto`].
+    fn load_from_path_auto_async(path: &Path) -> AsyncResult<'_, T>
+    where
+        T: Migrate + Send,
+    {
+        let span = crate::trace::file_span(
+            crate::trace::Operation::Load,
+            std::any::type_name::<T>(),
+            path,
+        );
+        Box::pin(crate::trace::instrument(span, async move {
+            if !path.exists() {
+                return Err(SerdeFlowError::FileNotFound.at_path(path));
+            }
+            let bytes = read_async(path)
+                .await
+                .map_err(|error| SerdeFlowError::from(error).at_path(path))?;
+            decode_auto(&bytes).map_err(|error| error.at_path(path))
+        }))
+    }
 }
 
 pub trait FileMigrateAsync<T: FileAsync<T>> {
-    fn load_and_migrate_async<E: FlowEncoder>(path: &Path) -> AsyncResult<T>;
-    fn migrate_async<E: FlowEncoder>(path: &Path) -> AsyncResult<()>;
+    fn load_and_migrate_async<E: FlowEncoder>(path: &Path) -> AsyncResult<'_, T>;
+    fn migrate_async<E: FlowEncoder>(path: &Path) -> AsyncResult<'_, ()>;
+
+    /// Loads the file with the encoder `S`, migrates it to the current variant and saves it atomically with the encoder `D`.
+    /// With the `file(backup)` option the original file is kept as a backup.
+    /// A file already stored with the encoder `D` and the current variant is left untouched.
+    fn migrate_encoding_async<S: FlowEncoder, D: FlowEncoder>(path: &Path) -> AsyncResult<'_, ()>;
+
+    /// Restores the file from the most recent backup created by a migration with the `file(backup)` option.
+    /// Returns the variant of the restored file.
+    fn rollback_async(path: &Path) -> AsyncResult<'_, u16> {
+        rollback_async(path)
+    }
+}
+
+pub(crate) fn rollback_async(path: &Path) -> AsyncResult<'_, u16> {
+    #[cfg(feature = "async-std-fs")]
+    let future = crate::fs::rollback_async_std(path);
+    #[cfg(all(feature = "tokio-fs", not(feature = "async-std-fs")))]
+    let future = crate::fs::rollback_tokio(path);
+    #[cfg(not(any(feature = "tokio-fs", feature = "async-std-fs")))]
+    let future = async move { crate::fs::rollback(path) };
+    Box::pin(future)
+}
+
+async fn read_async(path: &Path) -> std::io::Result<Vec<u8>> {
+    #[cfg(feature = "async-std-fs")]
+    return async_std::fs::read(path).await;
+    #[cfg(all(feature = "tokio-fs", not(feature = "async-std-fs")))]
+    return tokio::fs::read(path).await;
+    #[cfg(not(any(feature = "tokio-fs", feature = "async-std-fs")))]
+    std::fs::read(path)
 }
 
 pub trait Bytes<T> {
-    fn encode<E: FlowEncoder>(&self) -> FlowResult<Vec<u8>>;
-    fn decode<E: FlowEncoder>(bytes: &[u8]) -> FlowResult<T>;
+    fn encode<E: FlowEncoder>(&self) -> FlowResult<Vec<u8>> {
+        self.encode_with(&StaticEncoder::<E>(PhantomData))
+    }
+    fn decode<E: FlowEncoder>(bytes: &[u8]) -> FlowResult<T> {
+        Self::decode_with(&StaticEncoder::<E>(PhantomData), bytes)
+    }
+
+    /// Encodes the object with the configured encoder, e.g. `bincode::Encoder::with_options(..)`.
+    fn encode_with<C: FlowEncoderInstance>(&self, encoder: &C) -> FlowResult<Vec<u8>>;
+    /// Decodes the object with the configured encoder, e.g. `bincode::Encoder::with_options(..)`.
+    fn decode_with<C: FlowEncoderInstance>(encoder: &C, bytes: &[u8]) -> FlowResult<T>;
+
+    /// Decodes the object with the encoder recorded in the header of the bytes, see [`decode_auto`].
+    fn decode_auto(bytes: &[u8]) -> FlowResult<T>
+    where
+        T: Migrate,
+    {
+        decode_auto(bytes)
+    }
+}
+
+/// Decodes payloads of the type's own variant and of all the variants it can be migrated from.
+///
+/// Implemented by `#[derive(Flow)]`. Every type lists the variants it converts from with `#[variants(..)]`,
+/// a listed variant can list its own predecessors, so a payload of `UserV1` is decoded by `User` through
+/// `UserV1 -> UserV2 -> User` when `User` lists only `UserV2` and `UserV2` lists `UserV1`.
+pub trait Migrate: Sized {
+    /// Returns true if the payload of the variant can be decoded into the type.
+    fn accepts_variant(variant: u16) -> bool;
+
+    /// Decodes the payload of the variant and migrates it into the type.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::VariantUnknown` if the variant can't be migrated into the type,
+    /// and `SerdeFlowError::VariantUnsupported` if it's newer than the variant of the type.
+    fn decode_variant<E: FlowEncoder>(variant: u16, payload: &[u8]) -> FlowResult<Self> {
+        Self::decode_variant_with(&StaticEncoder::<E>(PhantomData), variant, payload)
+    }
+
+    /// Decodes the payload of the variant with the configured encoder and migrates it into the type.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::VariantUnknown` if the variant can't be migrated into the type,
+    /// and `SerdeFlowError::VariantUnsupported` if it's newer than the variant of the type.
+    fn decode_variant_with<C: FlowEncoderInstance>(
+        encoder: &C,
+        variant: u16,
+        payload: &[u8],
+    ) -> FlowResult<Self>;
+
+    /// Returns true if the payload of the variant produced by an encoder of the other family can be decoded into the type,
+    /// i.e. a zerocopy variant listed with `#[variants(zerocopy(..))]`.
+    fn accepts_foreign_variant(_variant: u16) -> bool {
+        false
+    }
+
+    /// Decodes the payload of the variant produced by the encoder `foreign_encoder` of the other family
+    /// and migrates it into the type, `encoder` is the one the type is decoded with.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::EncoderMismatch` if the type has no variants encoded by `foreign_encoder`.
+    fn decode_foreign<C: FlowEncoderInstance>(
+        encoder: &C,
+        foreign_encoder: u8,
+        _variant: u16,
+        _payload: &[u8],
+    ) -> FlowResult<Self> {
+        Err(SerdeFlowError::EncoderMismatch {
+            expected: encoder.id(),
+            found: foreign_encoder,
+        })
+    }
+}
+
+/// Converts the object of the variant `from_variant` into the variant `to_variant` with its `TryFrom` implementation.
+///
+/// Used by `#[derive(Flow)]` for every step of a migration. Every `From` implementation provides `TryFrom`,
+/// so infallible migrations never fail here.
+///
+/// # Errors
+///
+/// Returns a `SerdeFlowError::MigrationFailed` holding the error of the conversion.
+pub fn migrate_variant<F, T>(from_variant: u16, to_variant: u16, object: F) -> FlowResult<T>
+where
+    T: TryFrom<F>,
+    T::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let object = T::try_from(object).map_err(|error| SerdeFlowError::MigrationFailed {
+        from_variant,
+        to_variant,
+        source: error.into(),
+    })?;
+    crate::trace::migrated(std::any::type_name::<T>(), from_variant, to_variant);
+    Ok(object)
+}
+
+/// Encodes the object with the header of the variant, the payload is serialized directly behind the header.
+///
+/// # Errors
+///
+/// Returns an error if the object can't be serialized.
+pub fn encode<T: Serialize, E: FlowEncoder>(object: &T, variant: u16) -> FlowResult<Vec<u8>> {
+    encode_with(&StaticEncoder::<E>(PhantomData), object, variant)
+}
+
+/// Encodes the object with the configured encoder, see [`encode`].
+///
+/// # Errors
+///
+/// Returns an error if the object can't be serialized.
+pub fn encode_with<T: Serialize, C: FlowEncoderInstance>(
+    encoder: &C,
+    object: &T,
+    variant: u16,
+) -> FlowResult<Vec<u8>> {
+    let started = crate::trace::start();
+    let bytes = header::Header::new(encoder.id(), variant)
+        .with_flags(encoder.flags())
+        .with_payload_writer(|buffer| encoder.serialize_into(object, buffer))?;
+    crate::trace::encoded(
+        std::any::type_name::<T>(),
+        encoder.id(),
+        variant,
+        bytes.len(),
+        started,
+    );
+    Ok(bytes)
+}
+
+/// Parses the header of the bytes and decodes the payload into the type, migrating it from older variants.
+/// Payloads produced by another encoder are decoded only by the variants listed with `#[variants(zerocopy(..))]`.
+/// Bytes written by releases before the header are decoded with [`FlowEncoder::legacy_payload`].
+///
+/// # Errors
+///
+/// Returns an error if the header is invalid, the payload is corrupted or its variant can't be migrated.
+pub fn decode<T: Migrate, E: FlowEncoder>(bytes: &[u8]) -> FlowResult<T> {
+    decode_with(&StaticEncoder::<E>(PhantomData), bytes)
+}
+
+/// Decodes the bytes with the configured encoder, see [`decode`].
+///
+/// # Errors
+///
+/// Returns an error if the header is invalid, the payload is corrupted or its variant can't be migrated.
+pub fn decode_with<T: Migrate, C: FlowEncoderInstance>(encoder: &C, bytes: &[u8]) -> FlowResult<T> {
+    let started = crate::trace::start();
+    if header::is_legacy(bytes) {
+        let (variant, payload) = encoder.legacy_payload(bytes)?;
+        let object = T::decode_variant_with(encoder, variant, &payload)?;
+        crate::trace::decoded(
+            std::any::type_name::<T>(),
+            encoder.id(),
+            variant,
+            bytes.len(),
+            started,
+        );
+        return Ok(object);
+    }
+
+    let (header, payload) = header::Header::split(bytes)?;
+    header.verify(payload)?;
+    let object = if header.encoder == encoder.id() {
+        header.expect_encoder_flags(encoder.flags())?;
+        T::decode_variant_with(encoder, header.variant, payload)?
+    } else {
+        T::decode_foreign(encoder, header.encoder, header.variant, payload)?
+    };
+    crate::trace::decoded(
+        std::any::type_name::<T>(),
+        header.encoder,
+        header.variant,
+        bytes.len(),
+        started,
+    );
+    Ok(object)
+}
+
+/// Returns the variant of the bytes, read from the header or, with the encoder `E`, from the layout written before it.
+///
+/// # Errors
+///
+/// Returns the errors of [`header::Header::parse`] and [`FlowEncoder::legacy_payload`].
+pub fn stored_variant<E: FlowEncoder>(bytes: &[u8]) -> FlowResult<u16> {
+    if header::is_legacy(bytes) {
+        return E::legacy_payload(bytes).map(|(variant, _)| variant);
+    }
+    header::Header::parse(bytes).map(|header| header.variant)
+}
+
+/// Parses the header of the bytes and decodes the payload with the encoder recorded in the header,
+/// so the reader doesn't have to know whether the bytes were written by `bincode::Encoder` or `json::Encoder`.
+///
+/// Only the encoders shipped with serde_flow and enabled by features are detected. Payloads of the other encoders
+/// are decoded by the variants of the other family, e.g. zerocopy archives by the types listed with
+/// `#[variants(zerocopy(..))]`, see [`Migrate::decode_foreign`].
+/// Bytes written by releases before the header don't record their encoder, they're decoded as JSON if they start
+/// with `{` and with bincode otherwise.
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use serde_flow::encoder::{bincode, json};
+/// use serde_flow::flow::Bytes;
+/// use serde_flow::Flow;
+///
+/// #[derive(Serialize, Deserialize, Flow)]
+/// #[flow(variant = 1, bytes)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// let bytes = User { name: "John".to_string() }.encode::<json::Encoder>().unwrap();
+/// assert_eq!(User::decode_auto(&bytes).unwrap().name, "John");
+///
+/// let bytes = User { name: "Jane".to_string() }.encode::<bincode::Encoder>().unwrap();
+/// assert_eq!(User::decode_auto(&bytes).unwrap().name, "Jane");
+/// ```
+///
+/// # Errors
+///
+/// Returns a `SerdeFlowError::EncoderUnsupported` if the encoder of the header isn't available
+/// and the errors of [`decode`] otherwise.
+pub fn decode_auto<T: Migrate>(bytes: &[u8]) -> FlowResult<T> {
+    if header::is_legacy(bytes) {
+        return decode_legacy_auto(bytes);
+    }
+    let started = crate::trace::start();
+    let (header, payload) = header::Header::split(bytes)?;
+    header.verify(payload)?;
+    let object = decode_variant_auto::<T>(&header, payload)?;
+    crate::trace::decoded(
+        std::any::type_name::<T>(),
+        header.encoder,
+        header.variant,
+        bytes.len(),
+        started,
+    );
+    Ok(object)
+}
+
+/// Decodes bytes written by releases before the header, see [`decode_auto`].
+/// The object of a JSON payload starts with `{`, a bincode payload starts with the variant.
+fn decode_legacy_auto<T: Migrate>(bytes: &[u8]) -> FlowResult<T> {
+    match bytes.iter().find(|byte| !byte.is_ascii_whitespace()) {
+        #[cfg(feature = "json")]
+        Some(b'{') => decode::<T, crate::encoder::json::Encoder>(bytes),
+        #[cfg(feature = "bincode")]
+        _ => decode::<T, crate::encoder::bincode::Encoder>(bytes),
+        #[cfg(not(feature = "bincode"))]
+        _ => Err(SerdeFlowError::FormatInvalid),
+    }
+}
+
+/// Decodes the payload with the encoder and the options recorded in the header, see [`decode_auto`].
+///
+/// Used by `#[derive(Flow)]` to decode the serde variants of zerocopy types listed with `#[variants(serde(..))]`.
+///
+/// # Errors
+///
+/// Returns a `SerdeFlowError::EncoderUnsupported` if the encoder isn't available and no variant of the other family
+/// decodes its payloads, and the errors of [`Migrate::decode_variant`] and [`Migrate::decode_foreign`] otherwise.
+pub fn decode_variant_auto<T: Migrate>(header: &header::Header, payload: &[u8]) -> FlowResult<T> {
+    let variant = header.variant;
+    match header.encoder {
+        #[cfg(feature = "bincode")]
+        crate::encoder::bincode::Encoder::ID => {
+            let encoder = crate::encoder::bincode::Config::from_flags(header.encoder_flags());
+            T::decode_variant_with(&encoder, variant, payload)
+        }
+        #[cfg(feature = "json")]
+        crate::encoder::json::Encoder::ID => {
+            T::decode_variant::<crate::encoder::json::Encoder>(variant, payload)
+        }
+        found => T::decode_foreign(&UnsupportedEncoder(found), found, variant, payload).map_err(
+            |error| match error {
+                SerdeFlowError::EncoderMismatch { .. } => SerdeFlowError::EncoderUnsupported(found),
+                error => error,
+            },
+        ),
+    }
+}
+
+/// The encoder passed to [`Migrate::decode_foreign`] by [`decode_variant_auto`] for the encoders it doesn't detect.
+/// The variants of the other family don't use it, the serde variants fail with `SerdeFlowError::EncoderUnsupported`.
+struct UnsupportedEncoder(u8);
+
+impl FlowEncoderInstance for UnsupportedEncoder {
+    fn id(&self) -> u8 {
+        self.0
+    }
+
+    fn serialize_into<T: Serialize>(&self, _value: &T, _buffer: &mut Vec<u8>) -> FlowResult<()> {
+        Err(SerdeFlowError::EncoderUnsupported(self.0))
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, _bytes: &[u8]) -> FlowResult<T> {
+        Err(SerdeFlowError::EncoderUnsupported(self.0))
+    }
 }
 
+/// The variant written at the start of the payloads by releases before the header, see [`header::is_legacy`].
 #[cfg(feature = "serde")]
-#[derive(Serialize, Deserialize)]
+#[derive(serde::Deserialize)]
 pub struct FlowId {
     pub flow_id: u16,
 }
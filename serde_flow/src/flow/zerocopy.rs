@@ -1,7 +1,14 @@
 use std::path::{Path, PathBuf};
 
-use super::{AsyncResult, FlowResult};
-use crate::encoder::zerocopy::Reader;
+use rkyv::AlignedVec;
+
+use super::header::{Header, HEADER_SIZE};
+use super::{AsyncResult, FlowInfo, FlowResult};
+#[cfg(feature = "mmap")]
+use crate::encoder::zerocopy::MmapReader;
+use crate::encoder::zerocopy::{self, ArchivedView, Reader};
+use crate::error::SerdeFlowError;
+use crate::trace;
 
 pub trait File<T>
 where
@@ -10,6 +17,47 @@ where
 {
     fn load_from_path(path: &Path) -> FlowResult<Reader<T>>;
     fn save_to_path(&self, path: &Path) -> FlowResult<()>;
+
+    /// Loads the archive from the memory-mapped file without copying it into memory.
+    ///
+    /// Files of the older variants are migrated and saved by [`File::load_from_path`] first,
+    /// only the files of the current variant are read without copying.
+    #[cfg(feature = "mmap")]
+    fn load_from_path_mmap(path: &Path) -> FlowResult<MmapReader<T>>
+    where
+        T: FlowInfo,
+    {
+        let span = trace::file_span(trace::Operation::Load, std::any::type_name::<T>(), path);
+        span.in_scope(|| {
+            if !path.exists() {
+                return Err(SerdeFlowError::FileNotFound);
+            }
+            let mut mmap = zerocopy::map(path)?;
+            let is_current = !super::header::is_legacy(&mmap) && {
+                let header = Header::parse(&mmap)?;
+                header.encoder == zerocopy::Encoder::ID && header.variant == T::VARIANT
+            };
+            if !is_current {
+                // the mapping is released before the file is replaced
+                drop(mmap);
+                let _ = Self::load_from_path(path)?;
+                mmap = zerocopy::map(path)?;
+            }
+
+            let started = trace::start();
+            let size = mmap.len();
+            let reader = MmapReader::new(mmap)?;
+            trace::decoded(
+                std::any::type_name::<T>(),
+                zerocopy::Encoder::ID,
+                T::VARIANT,
+                size,
+                started,
+            );
+            Ok(reader)
+        })
+        .map_err(|error| error.at_path(path))
+    }
 }
 
 pub trait FileMigrate<T>
@@ -19,6 +67,12 @@ where
 {
     fn load_and_migrate(path: &Path) -> FlowResult<Reader<T>>;
     fn migrate(path: &Path) -> FlowResult<()>;
+
+    /// Restores the file from the most recent backup created by a migration with the `file(backup)` option.
+    /// Returns the variant of the restored file.
+    fn rollback(path: &Path) -> FlowResult<u16> {
+        crate::fs::rollback(path)
+    }
 }
 
 pub trait FileAsync<T>
@@ -26,8 +80,8 @@ where
     T: rkyv::Archive + rkyv::Serialize<crate::encoder::zerocopy::DefaultSerializer>,
     T::Archived: for<'b> rkyv::CheckBytes<rkyv::validation::validators::DefaultValidator<'b>>,
 {
-    fn load_from_path_async<'a>(path: PathBuf) -> AsyncResult<'a, Reader<'a, T>>;
-    fn save_to_path_async(&self, path: PathBuf) -> AsyncResult<()>;
+    fn load_from_path_async(path: PathBuf) -> AsyncResult<'static, Reader<T>>;
+    fn save_to_path_async(&self, path: PathBuf) -> AsyncResult<'_, ()>;
 }
 
 pub trait FileMigrateAsync<T>
@@ -35,8 +89,14 @@ where
     T: rkyv::Archive + rkyv::Serialize<crate::encoder::zerocopy::DefaultSerializer>,
     T::Archived: for<'b> rkyv::CheckBytes<rkyv::validation::validators::DefaultValidator<'b>>,
 {
-    fn load_and_migrate_async(path: &Path) -> AsyncResult<Reader<T>>;
-    fn migrate_async(path: &Path) -> AsyncResult<()>;
+    fn load_and_migrate_async(path: &Path) -> AsyncResult<'_, Reader<T>>;
+    fn migrate_async(path: &Path) -> AsyncResult<'_, ()>;
+
+    /// Restores the file from the most recent backup created by a migration with the `file(backup)` option.
+    /// Returns the variant of the restored file.
+    fn rollback_async(path: &Path) -> AsyncResult<'_, u16> {
+        super::rollback_async(path)
+    }
 }
 
 pub trait Bytes<T>
@@ -45,5 +105,133 @@ where
     T::Archived: for<'b> rkyv::CheckBytes<rkyv::validation::validators::DefaultValidator<'b>>,
 {
     fn encode(&self) -> FlowResult<Vec<u8>>;
-    fn decode(bytes: Vec<u8>) -> FlowResult<Reader<'static, T>>;
+    fn decode(bytes: Vec<u8>) -> FlowResult<Reader<T>>;
+
+    /// Encodes the object with the header into the buffer, replacing its content and reusing its allocation.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::Encode` if the encoding process fails.
+    fn encode_into(&self, buffer: &mut AlignedVec) -> FlowResult<()>
+    where
+        Self: Sized + rkyv::Serialize<crate::encoder::zerocopy::DefaultSerializer> + FlowInfo,
+    {
+        let started = trace::start();
+        buffer.clear();
+        buffer.extend_from_slice(&[0; HEADER_SIZE]);
+        zerocopy::Encoder::serialize_into(self, buffer)?;
+        Header::new(zerocopy::Encoder::ID, Self::VARIANT).write_to(buffer);
+        trace::encoded(
+            std::any::type_name::<Self>(),
+            zerocopy::Encoder::ID,
+            Self::VARIANT,
+            buffer.len(),
+            started,
+        );
+        Ok(())
+    }
+
+    /// Decodes the bytes produced by [`Bytes::encode`] in place, the archive is validated and borrowed without copying.
+    ///
+    /// The bytes must be aligned to [`AlignedVec::ALIGNMENT`], e.g. the buffer filled by [`Bytes::encode_into`].
+    /// Only the current variant can be read in place, use [`Bytes::decode`] to migrate the older variants.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::EncoderMismatch` if the payload wasn't produced by the zerocopy encoder,
+    /// `SerdeFlowError::VariantMismatch` if it holds an older variant and `SerdeFlowError::VariantUnsupported` if a newer one,
+    /// `SerdeFlowError::ChecksumMismatch` if it was corrupted and `SerdeFlowError::Decode` if it isn't an aligned archive of `T`.
+    fn decode_ref(bytes: &[u8]) -> FlowResult<ArchivedView<'_, T>>
+    where
+        T: FlowInfo,
+    {
+        let started = trace::start();
+        let (header, payload) = Header::split(bytes)?;
+        header.expect_encoder(zerocopy::Encoder::ID)?;
+        if header.variant > T::VARIANT {
+            return Err(SerdeFlowError::variant(
+                std::any::type_name::<T>(),
+                header.variant,
+                T::VARIANT,
+            ));
+        }
+        if header.variant != T::VARIANT {
+            return Err(SerdeFlowError::VariantMismatch {
+                type_name: std::any::type_name::<T>(),
+                expected: T::VARIANT,
+                found: header.variant,
+            });
+        }
+        header.verify(payload)?;
+        let view = ArchivedView::new(payload)?;
+        trace::decoded(
+            std::any::type_name::<T>(),
+            header.encoder,
+            header.variant,
+            bytes.len(),
+            started,
+        );
+        Ok(view)
+    }
+}
+
+/// Splits the bytes written by releases before the header into the variant and the archive,
+/// see [`super::header::is_legacy`]. The archive isn't aligned, it's copied when it's deserialized.
+///
+/// # Errors
+///
+/// Returns a `SerdeFlowError::FormatInvalid` if the bytes are too short to hold the variant.
+pub fn legacy_payload(bytes: &[u8]) -> FlowResult<(u16, &[u8])> {
+    match bytes {
+        [low, high, payload @ ..] => Ok((u16::from_le_bytes([*low, *high]), payload)),
+        _ => Err(SerdeFlowError::FormatInvalid),
+    }
+}
+
+/// Returns the variant of the bytes, read from the header or from the layout written before it.
+///
+/// # Errors
+///
+/// Returns the errors of [`Header::parse`] and [`legacy_payload`].
+pub fn stored_variant(bytes: &[u8]) -> FlowResult<u16> {
+    if super::header::is_legacy(bytes) {
+        return legacy_payload(bytes).map(|(variant, _)| variant);
+    }
+    Header::parse(bytes).map(|header| header.variant)
+}
+
+/// Deserializes payloads of the variants the zerocopy type can be migrated from.
+///
+/// Implemented by `#[derive(Flow)]`, see [`super::Migrate`] for how the variants are chained.
+pub trait Migrate: Sized {
+    /// Returns true if the payload of the variant can be deserialized into the type.
+    fn accepts_variant(variant: u16) -> bool;
+
+    /// Deserializes the archived payload of the variant and migrates it into the type.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::VariantUnknown` if the variant can't be migrated into the type,
+    /// and `SerdeFlowError::VariantUnsupported` if it's newer than the variant of the type.
+    /// The payload is copied into an aligned buffer, so it may have any alignment.
+    fn deserialize_variant(variant: u16, payload: &[u8]) -> FlowResult<Self>;
+
+    /// Returns true if the payload of the variant produced by a serde encoder can be decoded into the type,
+    /// i.e. a serde variant listed with `#[variants(serde(..))]`.
+    fn accepts_foreign_variant(_variant: u16) -> bool {
+        false
+    }
+
+    /// Decodes the payload produced by the serde encoder recorded in the header and migrates it into the type.
+    /// The encoders shipped with serde_flow are detected, see [`super::decode_variant_auto`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a `SerdeFlowError::EncoderMismatch` if the type has no variants encoded by the encoder of the header.
+    fn deserialize_foreign(header: &Header, _payload: &[u8]) -> FlowResult<Self> {
+        Err(SerdeFlowError::EncoderMismatch {
+            expected: crate::encoder::zerocopy::Encoder::ID,
+            found: header.encoder,
+        })
+    }
 }
@@ -0,0 +1,348 @@
+//! Crash-safe file operations used by the generated `File` and `FileAsync` implementations.
+//!
+//! Bytes are never written directly onto the target path. They are written into a sibling temporary file,
+//! which is flushed to the disk and renamed over the target, so the target always contains either the old
+//! or the new content. The temporary file gets the permissions of the replaced file, and a symlink at the target path
+//! is followed, so the file it points to is replaced or created rather than the symlink itself.
+//!
+//! Migrations of types with the `file(backup)` option keep the pre-migration file as `<name>.v<variant>.bak`
+//! next to the migrated file, which can be restored with [`rollback`].
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+use crate::error::SerdeFlowError;
+use crate::flow::FlowResult;
+
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Atomically replaces the content of the file at `path` with `bytes`.
+///
+/// # Errors
+///
+/// Returns an `std::io::Error` if the temporary file can't be written, synced or renamed.
+/// The target file is left untouched in this case.
+///
+/// ```
+/// # use tempfile::tempdir;
+/// # let temp_dir = tempdir().unwrap();
+/// # let path_buf = temp_dir.path().to_path_buf().join("file");
+/// # let path = path_buf.as_path();
+/// serde_flow::fs::write_atomic(path, b"content").unwrap();
+/// assert_eq!(std::fs::read(path).unwrap(), b"content");
+/// ```
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let (path, permissions) = match std::fs::canonicalize(path) {
+        Ok(target) => {
+            let permissions = std::fs::metadata(&target)?.permissions();
+            (target, Some(permissions))
+        }
+        Err(error) => (missing_target(error, path)?, None),
+    };
+    let temp_path = temp_path(&path)?;
+    let result = write_and_rename(&temp_path, &path, bytes, permissions);
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result?;
+    sync_dir(&path)
+}
+
+fn write_and_rename(
+    temp_path: &Path,
+    path: &Path,
+    bytes: &[u8],
+    permissions: Option<std::fs::Permissions>,
+) -> io::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(temp_path)?;
+    if let Some(permissions) = permissions {
+        file.set_permissions(permissions)?;
+    }
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    std::fs::rename(temp_path, path)
+}
+
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> io::Result<()> {
+    std::fs::File::open(parent_dir(path))?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Atomically replaces the content of the file at `path` with `bytes` using tokio.
+///
+/// # Errors
+///
+/// See [`write_atomic`].
+#[cfg(feature = "tokio-fs")]
+pub async fn write_atomic_tokio(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let (path, permissions) = match tokio::fs::canonicalize(path).await {
+        Ok(target) => {
+            let permissions = tokio::fs::metadata(&target).await?.permissions();
+            (target, Some(permissions))
+        }
+        Err(error) => (missing_target(error, path)?, None),
+    };
+    let temp_path = temp_path(&path)?;
+    let result = async {
+        use tokio::io::AsyncWriteExt;
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+            .await?;
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions).await?;
+        }
+        file.write_all(bytes).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&temp_path, &path).await
+    }
+    .await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+    }
+    result?;
+
+    #[cfg(unix)]
+    tokio::fs::File::open(parent_dir(&path))
+        .await?
+        .sync_all()
+        .await?;
+    Ok(())
+}
+
+/// Atomically replaces the content of the file at `path` with `bytes` using async-std.
+///
+/// # Errors
+///
+/// See [`write_atomic`].
+#[cfg(feature = "async-std-fs")]
+pub async fn write_atomic_async_std(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let (path, permissions) = match async_std::fs::canonicalize(path).await {
+        Ok(target) => {
+            let permissions = async_std::fs::metadata(&target).await?.permissions();
+            (target.into(), Some(permissions))
+        }
+        Err(error) => (missing_target(error, path)?, None),
+    };
+    let temp_path = temp_path(&path)?;
+    let result = async {
+        use async_std::io::WriteExt;
+
+        let mut file = async_std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+            .await?;
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions).await?;
+        }
+        file.write_all(bytes).await?;
+        file.sync_all().await?;
+        async_std::fs::rename(&temp_path, &path).await
+    }
+    .await;
+    if result.is_err() {
+        let _ = async_std::fs::remove_file(&temp_path).await;
+    }
+    result?;
+
+    #[cfg(unix)]
+    async_std::fs::File::open(parent_dir(&path))
+        .await?
+        .sync_all()
+        .await?;
+    Ok(())
+}
+
+/// Returns the path of the backup of the file at `path` stored with the variant `variant`.
+///
+/// ```
+/// use std::path::Path;
+///
+/// let backup = serde_flow::fs::backup_path(Path::new("/data/user"), 2);
+/// assert_eq!(backup, Path::new("/data/user.v2.bak"));
+/// ```
+#[must_use]
+pub fn backup_path(path: &Path, variant: u16) -> PathBuf {
+    let mut backup = path.as_os_str().to_os_string();
+    backup.push(format!(".v{variant}.bak"));
+    PathBuf::from(backup)
+}
+
+/// Copies the file at `path`, stored with the variant `variant`, into its backup file.
+///
+/// # Errors
+///
+/// Returns an `std::io::Error` if the file can't be read or the backup can't be written.
+pub fn backup(path: &Path, variant: u16) -> io::Result<()> {
+    let bytes = std::fs::read(path)?;
+    write_atomic(&backup_path(path, variant), &bytes)
+}
+
+/// Copies the file at `path`, stored with the variant `variant`, into its backup file using tokio.
+///
+/// # Errors
+///
+/// See [`backup`].
+#[cfg(feature = "tokio-fs")]
+pub async fn backup_tokio(path: &Path, variant: u16) -> io::Result<()> {
+    let bytes = tokio::fs::read(path).await?;
+    write_atomic_tokio(&backup_path(path, variant), &bytes).await
+}
+
+/// Copies the file at `path`, stored with the variant `variant`, into its backup file using async-std.
+///
+/// # Errors
+///
+/// See [`backup`].
+#[cfg(feature = "async-std-fs")]
+pub async fn backup_async_std(path: &Path, variant: u16) -> io::Result<()> {
+    let bytes = async_std::fs::read(path).await?;
+    write_atomic_async_std(&backup_path(path, variant), &bytes).await
+}
+
+/// Restores the file at `path` from its most recent backup and returns the variant of the restored file.
+/// The backup file is moved over the migrated file.
+///
+/// # Errors
+///
+/// Returns a `SerdeFlowError::BackupNotFound` if there is no backup of the file,
+/// and `SerdeFlowError::IOError` if the backup can't be restored.
+pub fn rollback(path: &Path) -> FlowResult<u16> {
+    let mut latest: Option<(SystemTime, u16, PathBuf)> = None;
+    for entry in std::fs::read_dir(parent_dir(path))? {
+        let entry = entry?;
+        let Some(variant) = backup_variant(path, &entry.file_name()) else {
+            continue;
+        };
+        let modified = entry.metadata()?.modified()?;
+        latest = newest_backup(latest, (modified, variant, entry.path()));
+    }
+
+    let (_, variant, backup) = latest.ok_or(SerdeFlowError::BackupNotFound)?;
+    std::fs::rename(backup, path)?;
+    sync_dir(path)?;
+    Ok(variant)
+}
+
+/// Restores the file at `path` from its most recent backup using tokio.
+///
+/// # Errors
+///
+/// See [`rollback`].
+#[cfg(feature = "tokio-fs")]
+pub async fn rollback_tokio(path: &Path) -> FlowResult<u16> {
+    let mut latest: Option<(SystemTime, u16, PathBuf)> = None;
+    let mut entries = tokio::fs::read_dir(parent_dir(path)).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let Some(variant) = backup_variant(path, &entry.file_name()) else {
+            continue;
+        };
+        let modified = entry.metadata().await?.modified()?;
+        latest = newest_backup(latest, (modified, variant, entry.path()));
+    }
+
+    let (_, variant, backup) = latest.ok_or(SerdeFlowError::BackupNotFound)?;
+    tokio::fs::rename(backup, path).await?;
+    #[cfg(unix)]
+    tokio::fs::File::open(parent_dir(path))
+        .await?
+        .sync_all()
+        .await?;
+    Ok(variant)
+}
+
+/// Restores the file at `path` from its most recent backup using async-std.
+///
+/// # Errors
+///
+/// See [`rollback`].
+#[cfg(feature = "async-std-fs")]
+pub async fn rollback_async_std(path: &Path) -> FlowResult<u16> {
+    use async_std::stream::StreamExt;
+
+    let mut latest: Option<(SystemTime, u16, PathBuf)> = None;
+    let mut entries = async_std::fs::read_dir(parent_dir(path)).await?;
+    while let Some(entry) = entries.next().await {
+        let entry = entry?;
+        let Some(variant) = backup_variant(path, &entry.file_name()) else {
+            continue;
+        };
+        let modified = entry.metadata().await?.modified()?;
+        latest = newest_backup(latest, (modified, variant, entry.path().into()));
+    }
+
+    let (_, variant, backup) = latest.ok_or(SerdeFlowError::BackupNotFound)?;
+    async_std::fs::rename(backup, path).await?;
+    #[cfg(unix)]
+    async_std::fs::File::open(parent_dir(path))
+        .await?
+        .sync_all()
+        .await?;
+    Ok(variant)
+}
+
+/// Returns the variant of the backup, if `name` is the name of a backup of the file at `path`.
+fn backup_variant(path: &Path, name: &std::ffi::OsStr) -> Option<u16> {
+    let file_name = path.file_name()?.to_str()?;
+    name.to_str()?
+        .strip_prefix(file_name)?
+        .strip_prefix(".v")?
+        .strip_suffix(".bak")?
+        .parse()
+        .ok()
+}
+
+fn newest_backup(
+    latest: Option<(SystemTime, u16, PathBuf)>,
+    candidate: (SystemTime, u16, PathBuf),
+) -> Option<(SystemTime, u16, PathBuf)> {
+    match latest {
+        Some(latest) if (latest.0, latest.1) >= (candidate.0, candidate.1) => Some(latest),
+        _ => Some(candidate),
+    }
+}
+
+/// Returns the path to write when the target can't be resolved: the path itself if the file doesn't exist yet,
+/// the file a dangling symlink points to, and the error otherwise.
+fn missing_target(error: io::Error, path: &Path) -> io::Result<PathBuf> {
+    if error.kind() != io::ErrorKind::NotFound {
+        return Err(error);
+    }
+    let mut path = path.to_path_buf();
+    while std::fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+        // a relative symlink is resolved against the directory containing it
+        path = parent_dir(&path).join(std::fs::read_link(&path)?);
+    }
+    Ok(path)
+}
+
+/// Creates a unique path for the temporary file next to the target file.
+fn temp_path(path: &Path) -> io::Result<PathBuf> {
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "path doesn't point to a file")
+    })?;
+    let counter = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.{counter}.tmp", std::process::id()));
+    Ok(parent_dir(path).join(temp_name))
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
@@ -80,6 +80,8 @@
 //!
 //! To use *migrations*, you need to tell the program about different ways your data can be saved (called "variants"). Migrations works well with text formats, like JSON. To do this, add a special instruction called ``[#[variants(StructA, StructB, ...)]`` and list all the ways your data can be saved.
 //!
+//! A listed variant can list its own predecessors, so every struct only needs a `From` (or a fallible `TryFrom`) conversion from the variants it lists and the migration is composed along the chain (`UserV1 -> UserV2 -> User`).
+//!
 //! ## Setup ``File Mode`` for Serde serialization
 //!
 //! Implements basic serde struct for serializing and deserializing User with version 1.
@@ -141,6 +143,72 @@
 //! }
 //! ```
 //!
+//! ## Backup
+//!
+//! To keep the original file when it's migrated to a new variant, add the ``backup`` option. The file of the old variant is kept as ``<name>.v<old_variant>.bak`` and can be restored with ``rollback``.
+//!
+//! ```rust
+//! use serde::{Deserialize, Serialize};
+//! use serde_flow::{encoder::bincode, flow::File, flow::FileMigrate, Flow};
+//! # use serde_flow::flow::FlowResult;
+//! # use tempfile::tempdir;
+//!
+//! #[derive(Flow, Serialize, Deserialize)]
+//! #[flow(variant = 2, file(backup))]
+//! #[variants(UserV1)]
+//! struct User {
+//!     name: String
+//! }
+//! # #[derive(Flow, Serialize, Deserialize)]
+//! # #[flow(variant = 1, file)]
+//! # struct UserV1 {
+//! #     value: u16
+//! # }
+//! # impl From<UserV1> for User {
+//! #     fn from(object: UserV1) -> User {
+//! #         User { name: object.value.to_string() }
+//! #     }
+//! # }
+//! # fn main() -> FlowResult<()> {
+//! # let temp_dir = tempdir().unwrap();
+//! # let path_buf = temp_dir.path().to_path_buf().join("user");
+//! # let path = path_buf.as_path();
+//! # UserV1 { value: 123 }.save_to_path::<bincode::Encoder>(path)?;
+//! // migrates the file and keeps the old one as `user.v1.bak`
+//! User::migrate::<bincode::Encoder>(path)?;
+//! // restores `user.v1.bak`
+//! let variant = User::rollback(path)?;
+//! assert_eq!(variant, 1);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Default Encoder
+//!
+//! To stop passing the encoder on every call, bind it with ``encoder = bincode``, ``encoder = json`` or a path to your own encoder. This generates ``load``/``save``, ``load_async``/``save_async`` and ``to_bytes``/``from_bytes``.
+//!
+//! ```rust
+//! use serde::{Deserialize, Serialize};
+//! use serde_flow::{Flow};
+//! # use serde_flow::flow::FlowResult;
+//! # use tempfile::tempdir;
+//!
+//! #[derive(Flow, Serialize, Deserialize)]
+//! #[flow(variant = 1, file, bytes, encoder = bincode)]
+//! struct User {
+//!     name: String
+//! }
+//! # fn main() -> FlowResult<()> {
+//! # let temp_dir = tempdir().unwrap();
+//! # let path_buf = temp_dir.path().to_path_buf().join("user");
+//! # let path = path_buf.as_path();
+//! User { name: "John".to_string() }.save(path)?;
+//! let user = User::load(path)?;
+//! let bytes = user.to_bytes()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ## Usage
 //!
 //! You have to include some imports to use migrations.
@@ -257,7 +325,7 @@
 //!
 //! #### Reader
 //!
-//! With the ``Reader<T>`` trait, you can do two things: map exact bytes of the loaded file into immutable object (called "archive") or decode and copy information from a loaded file (called "deserialize"). The ``archive`` method uses zero-copy, while ``deserialize`` doesn't use it.
+//! With the ``Reader<T>``, you can do two things: map exact bytes of the loaded file into immutable object (called "archive") or decode and copy information from a loaded file (called "deserialize"). The archive is validated once when the file is loaded, the reader dereferences to it without copying, while ``deserialize`` copies it. The reader owns its bytes, it's ``Send + Sync`` whenever the archived type is and can then be shared in an ``Arc``.
 //!
 //! ```rust
 //! use serde_flow::{flow::zerocopy::File, Flow};
@@ -278,9 +346,10 @@
 //! # let user = User { name: "Jan Janssen".to_string() };
 //! # let _ = user.save_to_path(path)?;
 //! let user_reader = User::load_from_path(path)?;
-//! let user_archived = user_reader.archive()?;
+//! assert_eq!(user_reader.name, "Jan Janssen".to_string());
 //!
-//! assert_eq!(user_archived.name, "Jan Janssen".to_string());
+//! let user = user_reader.deserialize()?;
+//! assert_eq!(user.name, "Jan Janssen".to_string());
 //! # Ok(())
 //! # }
 //! ```
@@ -316,6 +385,9 @@
 pub mod encoder;
 pub mod error;
 pub mod flow;
+pub mod fs;
+pub mod trace;
 
 extern crate serde_flow_derive;
+pub use flow::FlowInfo;
 pub use serde_flow_derive::Flow;
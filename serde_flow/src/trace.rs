@@ -0,0 +1,112 @@
+//! Spans and events of the loads, saves, migrations, encodings and decodings.
+//!
+//! With the `tracing` feature they are emitted with the target `serde_flow` at the `DEBUG` level,
+//! without it the functions do nothing. The payloads are never recorded.
+//! Used by the implementations generated by `#[derive(Flow)]`.
+use std::future::Future;
+use std::path::Path;
+
+/// Operation on a file, the name of its span.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Load,
+    Save,
+    Migrate,
+}
+
+/// The span of an operation on a file, `tracing::Span` with the `tracing` feature.
+#[cfg(feature = "tracing")]
+pub type Span = tracing::Span;
+
+/// The span of an operation on a file, `tracing::Span` with the `tracing` feature.
+#[cfg(not(feature = "tracing"))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Span;
+
+#[cfg(not(feature = "tracing"))]
+impl Span {
+    pub fn in_scope<F: FnOnce() -> T, T>(&self, f: F) -> T {
+        f()
+    }
+}
+
+/// The moment an encoding or decoding started, reported as `duration_us` of its event.
+#[derive(Debug, Clone, Copy)]
+pub struct Started(#[cfg(feature = "tracing")] std::time::Instant);
+
+#[must_use]
+pub fn start() -> Started {
+    Started(
+        #[cfg(feature = "tracing")]
+        std::time::Instant::now(),
+    )
+}
+
+/// Creates the span of the operation on the file at `path`.
+#[cfg(feature = "tracing")]
+#[must_use]
+pub fn file_span(operation: Operation, type_name: &'static str, path: &Path) -> Span {
+    let path = path.display();
+    match operation {
+        Operation::Load => {
+            tracing::debug_span!(target: "serde_flow", "load", type_name, path = %path)
+        }
+        Operation::Save => {
+            tracing::debug_span!(target: "serde_flow", "save", type_name, path = %path)
+        }
+        Operation::Migrate => {
+            tracing::debug_span!(target: "serde_flow", "migrate", type_name, path = %path)
+        }
+    }
+}
+
+/// Creates the span of the operation on the file at `path`.
+#[cfg(not(feature = "tracing"))]
+#[must_use]
+pub fn file_span(_operation: Operation, _type_name: &'static str, _path: &Path) -> Span {
+    Span
+}
+
+/// Runs the future of an async operation inside the span.
+#[cfg(feature = "tracing")]
+pub fn instrument<F: Future>(span: Span, future: F) -> impl Future<Output = F::Output> {
+    tracing::Instrument::instrument(future, span)
+}
+
+/// Runs the future of an async operation inside the span.
+#[cfg(not(feature = "tracing"))]
+pub fn instrument<F: Future>(_span: Span, future: F) -> impl Future<Output = F::Output> {
+    future
+}
+
+/// Reports the payload of `size` bytes encoded with the header of the variant.
+#[cfg(feature = "tracing")]
+pub fn encoded(type_name: &str, encoder: u8, variant: u16, size: usize, started: Started) {
+    let duration_us = u64::try_from(started.0.elapsed().as_micros()).unwrap_or(u64::MAX);
+    tracing::debug!(target: "serde_flow", type_name, encoder, variant, size, duration_us, "encode");
+}
+
+/// Reports the payload of `size` bytes encoded with the header of the variant.
+#[cfg(not(feature = "tracing"))]
+pub fn encoded(_type_name: &str, _encoder: u8, _variant: u16, _size: usize, _started: Started) {}
+
+/// Reports the payload of `size` bytes with the header of the variant decoded.
+#[cfg(feature = "tracing")]
+pub fn decoded(type_name: &str, encoder: u8, variant: u16, size: usize, started: Started) {
+    let duration_us = u64::try_from(started.0.elapsed().as_micros()).unwrap_or(u64::MAX);
+    tracing::debug!(target: "serde_flow", type_name, encoder, variant, size, duration_us, "decode");
+}
+
+/// Reports the payload of `size` bytes with the header of the variant decoded.
+#[cfg(not(feature = "tracing"))]
+pub fn decoded(_type_name: &str, _encoder: u8, _variant: u16, _size: usize, _started: Started) {}
+
+/// Reports the object migrated from one variant to another.
+#[cfg(feature = "tracing")]
+pub fn migrated(type_name: &str, from_variant: u16, to_variant: u16) {
+    tracing::debug!(target: "serde_flow", type_name, from_variant, to_variant, "migrate");
+}
+
+/// Reports the object migrated from one variant to another.
+#[cfg(not(feature = "tracing"))]
+pub fn migrated(_type_name: &str, _from_variant: u16, _to_variant: u16) {}
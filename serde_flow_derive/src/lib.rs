@@ -11,10 +11,7 @@ pub fn flow_derive(input: TokenStream) -> TokenStream {
     // parsing flow generator
     let flow_gen = match FlowGenerator::parse(input) {
         Ok(gen) => gen,
-        Err(e) => {
-            eprintln!("{e}");
-            return TokenStream::new();
-        }
+        Err(e) => return e.to_compile_error().into(),
     };
 
     let flow = flow_gen.generate();
@@ -30,57 +27,269 @@ struct FlowGenerator {
     is_bloking: bool,
     is_zerocopy: bool,
     is_verify_write: bool,
-    variants: Option<Vec<Ident>>,
-    fields_gen: FieldsGenerator,
+    is_backup: bool,
+    encoder: Option<syn::Path>,
+    generics: syn::Generics,
+    variants: Option<Vec<syn::Type>>,
+    /// Variants of the other encoding family, listed with `serde(..)` or `zerocopy(..)`.
+    foreign_variants: Vec<syn::Type>,
 }
 
 impl FlowGenerator {
     fn generate(&self) -> proc_macro2::TokenStream {
         let previous = self.generate_ids();
+        let previous = self.generate_id_checks(previous);
+        let previous = self.generate_migrate(previous);
         let previous = self.generate_bytes(previous);
-        self.generate_file(previous)
+        let previous = self.generate_file(previous);
+        self.generate_encoder_methods(previous)
     }
 
     fn generate_ids(&self) -> proc_macro2::TokenStream {
-        let struct_name = self.struct_name.clone();
         let variant = self.variant;
-        let flow_id_name = gen_variant_id_name(&struct_name);
-        let flow_variant_const_impl = quote! {
-            const #flow_id_name: u16 = #variant;
+        let self_type = self.self_type();
+        let type_name = self.struct_name.to_string();
+        let variants = self.all_variants();
+        let encoding = if self.is_zerocopy {
+            quote! { serde_flow::flow::Encoding::Zerocopy }
+        } else {
+            quote! { serde_flow::flow::Encoding::Serde }
         };
 
-        if self.is_zerocopy {
-            return flow_variant_const_impl;
+        let bounds: Vec<proc_macro2::TokenStream> = variants
+            .iter()
+            .map(|variant| quote! { #variant: serde_flow::FlowInfo })
+            .collect();
+        let (impl_generics, where_clause) = self.split_for_impl(&bounds);
+        let id_checks = self.generic_id_checks();
+        quote! {
+            impl #impl_generics serde_flow::FlowInfo for #self_type #where_clause {
+                const VARIANT: u16 = {
+                    #(#id_checks)*
+                    #variant
+                };
+                const TYPE_NAME: &'static str = concat!(module_path!(), "::", #type_name);
+                const ENCODING: serde_flow::flow::Encoding = #encoding;
+                const ACCEPTED_VARIANT_SET: serde_flow::flow::VariantSet = serde_flow::flow::VariantSet::new(#variant)
+                    #(.with(<#variants as serde_flow::FlowInfo>::ACCEPTED_VARIANTS))*;
+            }
+        }
+    }
+
+    /// Variant ids are constants known only to the compiler, so the conflicts between them
+    /// are reported by constant assertions spanned to the variants listed in `#[variants(..)]`.
+    /// The variants of generic types depend on the type parameters, see [`FlowGenerator::generic_id_checks`].
+    fn generate_id_checks(&self, previous: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        if !self.generics.params.is_empty() {
+            return previous;
+        }
+
+        let struct_name = &self.struct_name;
+        let variants = self.all_variants();
+        let mut checks = Vec::new();
+        for (index, variant) in variants.iter().enumerate() {
+            let name = type_name(variant);
+            let message = format!("variant `{name}` has the same id as `{struct_name}`");
+            checks.push(quote::quote_spanned! {variant.span()=>
+                const _: () = assert!(
+                    <#variant as serde_flow::FlowInfo>::VARIANT != <#struct_name as serde_flow::FlowInfo>::VARIANT,
+                    #message
+                );
+            });
+
+            for other in &variants[..index] {
+                let other_name = type_name(other);
+                let message = format!("variants `{other_name}` and `{name}` have the same id");
+                checks.push(quote::quote_spanned! {variant.span()=>
+                    const _: () = assert!(
+                        <#variant as serde_flow::FlowInfo>::VARIANT != <#other as serde_flow::FlowInfo>::VARIANT,
+                        #message
+                    );
+                });
+            }
         }
 
-        // prepare transformers for non zerocopy
-        let flow_id_struct_name = gen_variant_dto_name(&struct_name);
-        let fields = self.fields_gen.fields();
-        let field_names = self.fields_gen.field_names();
         quote! {
-            #flow_variant_const_impl
+            #previous
+            #(#checks)*
+        }
+    }
+
+    /// The checks of the variant ids of generic types, evaluated with the `VARIANT` constant of the type
+    /// once the type parameters are known. The id of the type is compared as a literal, because
+    /// comparing with `Self::VARIANT` would make the constant depend on itself.
+    fn generic_id_checks(&self) -> Vec<proc_macro2::TokenStream> {
+        if self.generics.params.is_empty() {
+            return Vec::new();
+        }
 
-            #[derive(serde::Serialize, serde::Deserialize)]
-            pub struct #flow_id_struct_name {
-                pub flow_id: u16,
-                #(#fields,)*
+        let variant = self.variant;
+        let variants = self.all_variants();
+        let mut checks = Vec::new();
+        for (index, current) in variants.iter().enumerate() {
+            checks.push(quote::quote_spanned! {current.span()=>
+                let () = serde_flow::flow::VariantId::<#current, #variant>::DISTINCT;
+            });
+            for other in &variants[..index] {
+                checks.push(quote::quote_spanned! {current.span()=>
+                    let () = serde_flow::flow::VariantIds::<#current, #other>::DISTINCT;
+                });
             }
+        }
+        checks
+    }
 
-            impl #flow_id_struct_name {
-                pub fn new(flow_id: u16, item: &#struct_name) -> Self {
-                    Self {
-                        flow_id,
-                        #(#field_names: item.#field_names.clone(),)*
-                    }
+    fn generate_migrate(&self, previous: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        let self_type = self.self_type();
+        let flow_id = quote! { <Self as serde_flow::FlowInfo>::VARIANT };
+        let variants = self.variants.clone().unwrap_or_default();
+        let variant_ids: Vec<proc_macro2::TokenStream> = variants
+            .iter()
+            .map(|variant| quote! { <#variant as serde_flow::FlowInfo>::VARIANT })
+            .collect();
+        let foreign_variants = &self.foreign_variants;
+        let foreign_variant_ids: Vec<proc_macro2::TokenStream> = foreign_variants
+            .iter()
+            .map(|variant| quote! { <#variant as serde_flow::FlowInfo>::VARIANT })
+            .collect();
+        let (migrate_trait, foreign_migrate_trait) = if self.is_zerocopy {
+            (
+                quote! { serde_flow::flow::zerocopy::Migrate },
+                quote! { serde_flow::flow::Migrate },
+            )
+        } else {
+            (
+                quote! { serde_flow::flow::Migrate },
+                quote! { serde_flow::flow::zerocopy::Migrate },
+            )
+        };
+
+        let mut bounds = self.encoding_bounds();
+        for variant in &variants {
+            bounds.push(quote! { #variant: serde_flow::FlowInfo + #migrate_trait });
+        }
+        for variant in foreign_variants {
+            bounds.push(quote! { #variant: serde_flow::FlowInfo + #foreign_migrate_trait });
+        }
+        for variant in variants.iter().chain(foreign_variants) {
+            bounds.push(quote! { #self_type: TryFrom<#variant> });
+            bounds.push(quote! {
+                <#self_type as TryFrom<#variant>>::Error: Into<Box<dyn std::error::Error + Send + Sync>>
+            });
+        }
+        let (impl_generics, where_clause) = self.split_for_impl(&bounds);
+
+        // payloads of the other family are decoded by the foreign variants listed directly,
+        // or by the variants of the same family which list them
+        let foreign_methods = if self.variants.is_none() {
+            quote! {}
+        } else if self.is_zerocopy {
+            quote! {
+                fn accepts_foreign_variant(variant: u16) -> bool {
+                    false
+                        #(|| <#foreign_variants as serde_flow::flow::Migrate>::accepts_variant(variant))*
+                        #(|| <#variants as serde_flow::flow::zerocopy::Migrate>::accepts_foreign_variant(variant))*
+                }
+                fn deserialize_foreign(header: &serde_flow::flow::header::Header, payload: &[u8]) -> serde_flow::flow::FlowResult<Self> {
+                    #(
+                        if <#foreign_variants as serde_flow::flow::Migrate>::accepts_variant(header.variant) {
+                            return serde_flow::flow::decode_variant_auto::<#foreign_variants>(header, payload).and_then(|object| serde_flow::flow::migrate_variant(#foreign_variant_ids, #flow_id, object));
+                        }
+                    )*
+                    #(
+                        if <#variants as serde_flow::flow::zerocopy::Migrate>::accepts_foreign_variant(header.variant) {
+                            return <#variants as serde_flow::flow::zerocopy::Migrate>::deserialize_foreign(header, payload).and_then(|object| serde_flow::flow::migrate_variant(#variant_ids, #flow_id, object));
+                        }
+                    )*
+                    Err(serde_flow::error::SerdeFlowError::EncoderMismatch {
+                        expected: serde_flow::encoder::zerocopy::Encoder::ID,
+                        found: header.encoder,
+                    })
+                }
+            }
+        } else {
+            quote! {
+                fn accepts_foreign_variant(variant: u16) -> bool {
+                    false
+                        #(|| <#foreign_variants as serde_flow::flow::zerocopy::Migrate>::accepts_variant(variant))*
+                        #(|| <#variants as serde_flow::flow::Migrate>::accepts_foreign_variant(variant))*
+                }
+                fn decode_foreign<C: serde_flow::encoder::FlowEncoderInstance>(encoder: &C, foreign_encoder: u8, variant: u16, payload: &[u8]) -> serde_flow::flow::FlowResult<Self> {
+                    #(
+                        if foreign_encoder == serde_flow::encoder::zerocopy::Encoder::ID
+                            && <#foreign_variants as serde_flow::flow::zerocopy::Migrate>::accepts_variant(variant)
+                        {
+                            return <#foreign_variants as serde_flow::flow::zerocopy::Migrate>::deserialize_variant(variant, payload).and_then(|object| serde_flow::flow::migrate_variant(#foreign_variant_ids, #flow_id, object));
+                        }
+                    )*
+                    #(
+                        if <#variants as serde_flow::flow::Migrate>::accepts_foreign_variant(variant) {
+                            return <#variants as serde_flow::flow::Migrate>::decode_foreign(encoder, foreign_encoder, variant, payload).and_then(|object| serde_flow::flow::migrate_variant(#variant_ids, #flow_id, object));
+                        }
+                    )*
+                    Err(serde_flow::error::SerdeFlowError::EncoderMismatch {
+                        expected: encoder.id(),
+                        found: foreign_encoder,
+                    })
                 }
             }
+        };
 
-            impl From<#flow_id_struct_name> for #struct_name {
-                fn from(item: #flow_id_struct_name) -> Self {
-                    #struct_name {
-                        #(#field_names: item.#field_names,)*
+        if self.is_zerocopy {
+            return quote! {
+                #previous
+                impl #impl_generics serde_flow::flow::zerocopy::Migrate for #self_type #where_clause {
+                    fn accepts_variant(variant: u16) -> bool {
+                        variant == #flow_id
+                            #(|| <#variants as serde_flow::flow::zerocopy::Migrate>::accepts_variant(variant))*
                     }
+                    fn deserialize_variant(variant: u16, payload: &[u8]) -> serde_flow::flow::FlowResult<Self> {
+                        if variant == #flow_id {
+                            return serde_flow::encoder::zerocopy::Reader::<Self>::from_slice(payload)?.deserialize();
+                        }
+                        // variants listed directly have priority over the chained ones
+                        #(
+                            if variant == #variant_ids {
+                                return <#variants as serde_flow::flow::zerocopy::Migrate>::deserialize_variant(variant, payload).and_then(|object| serde_flow::flow::migrate_variant(#variant_ids, #flow_id, object));
+                            }
+                        )*
+                        #(
+                            if <#variants as serde_flow::flow::zerocopy::Migrate>::accepts_variant(variant) {
+                                return <#variants as serde_flow::flow::zerocopy::Migrate>::deserialize_variant(variant, payload).and_then(|object| serde_flow::flow::migrate_variant(#variant_ids, #flow_id, object));
+                            }
+                        )*
+                        Err(serde_flow::error::SerdeFlowError::variant(std::any::type_name::<Self>(), variant, #flow_id))
+                    }
+                    #foreign_methods
                 }
+            };
+        }
+
+        quote! {
+            #previous
+            impl #impl_generics serde_flow::flow::Migrate for #self_type #where_clause {
+                fn accepts_variant(variant: u16) -> bool {
+                    variant == #flow_id
+                        #(|| <#variants as serde_flow::flow::Migrate>::accepts_variant(variant))*
+                }
+                fn decode_variant_with<C: serde_flow::encoder::FlowEncoderInstance>(encoder: &C, variant: u16, payload: &[u8]) -> serde_flow::flow::FlowResult<Self> {
+                    if variant == #flow_id {
+                        return encoder.deserialize::<Self>(payload);
+                    }
+                    // variants listed directly have priority over the chained ones
+                    #(
+                        if variant == #variant_ids {
+                            return <#variants as serde_flow::flow::Migrate>::decode_variant_with(encoder, variant, payload).and_then(|object| serde_flow::flow::migrate_variant(#variant_ids, #flow_id, object));
+                        }
+                    )*
+                    #(
+                        if <#variants as serde_flow::flow::Migrate>::accepts_variant(variant) {
+                            return <#variants as serde_flow::flow::Migrate>::decode_variant_with(encoder, variant, payload).and_then(|object| serde_flow::flow::migrate_variant(#variant_ids, #flow_id, object));
+                        }
+                    )*
+                    Err(serde_flow::error::SerdeFlowError::variant(std::any::type_name::<Self>(), variant, #flow_id))
+                }
+                #foreign_methods
             }
         }
     }
@@ -90,32 +299,35 @@ impl FlowGenerator {
             return previous;
         }
 
-        let struct_name = self.struct_name.clone();
+        let self_type = self.self_type();
+        let (impl_generics, where_clause) = self.split_for_impl(&self.migrate_bounds());
         let encode_with_version = self.encode_with_version();
         let decode_with_version = self.decode_with_version();
 
         if self.is_zerocopy {
             return quote! {
                 #previous
-                impl serde_flow::flow::zerocopy::Bytes<#struct_name> for #struct_name {
+                impl #impl_generics serde_flow::flow::zerocopy::Bytes<#self_type> for #self_type #where_clause {
                     fn encode(&self) -> serde_flow::flow::FlowResult<Vec<u8>> {
                         #encode_with_version
                         Ok(total_bytes)
                     }
-                    fn decode(bytes: Vec<u8>) -> serde_flow::flow::FlowResult<Reader<#struct_name>> {
+                    fn decode(bytes: Vec<u8>) -> serde_flow::flow::FlowResult<serde_flow::encoder::zerocopy::Reader<#self_type>> {
                         #decode_with_version
+                        let bytes = serde_flow::encoder::zerocopy::Encoder::serialize::<Self>(&migrated)?;
+                        serde_flow::encoder::zerocopy::Reader::<Self>::new(bytes)
                     }
                 }
             };
         }
         quote! {
             #previous
-            impl serde_flow::flow::Bytes<#struct_name> for #struct_name {
-                fn encode<E: serde_flow::encoder::FlowEncoder>(&self) -> serde_flow::flow::FlowResult<Vec<u8>> {
+            impl #impl_generics serde_flow::flow::Bytes<#self_type> for #self_type #where_clause {
+                fn encode_with<C: serde_flow::encoder::FlowEncoderInstance>(&self, encoder: &C) -> serde_flow::flow::FlowResult<Vec<u8>> {
                     #encode_with_version
                     Ok(total_bytes)
                 }
-                fn decode<E: serde_flow::encoder::FlowEncoder>(bytes: &[u8]) -> serde_flow::flow::FlowResult<#struct_name> {
+                fn decode_with<C: serde_flow::encoder::FlowEncoderInstance>(encoder: &C, bytes: &[u8]) -> serde_flow::flow::FlowResult<#self_type> {
                     #decode_with_version
                 }
             }
@@ -134,11 +346,90 @@ impl FlowGenerator {
         }
     }
 
+    /// Generates `load`/`save` methods bound to the encoder of `#[flow(encoder = ..)]`,
+    /// so the call sites can't mix up the encoders.
+    fn generate_encoder_methods(
+        &self,
+        previous: proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
+        let Some(encoder) = self.encoder_type() else {
+            return previous;
+        };
+        let self_type = self.self_type();
+
+        let mut generated = previous;
+        if self.is_file && self.is_bloking {
+            let (impl_generics, where_clause) = self.split_for_impl(&self.migrate_bounds());
+            generated = quote! {
+                #generated
+                impl #impl_generics #self_type #where_clause {
+                    /// Loads the object from the file with the encoder of the type, migrating older variants in memory.
+                    pub fn load(path: &std::path::Path) -> serde_flow::flow::FlowResult<Self> {
+                        <Self as serde_flow::flow::File<Self>>::load_from_path::<#encoder>(path)
+                    }
+                    /// Saves the object into the file with the encoder of the type.
+                    pub fn save(&self, path: &std::path::Path) -> serde_flow::flow::FlowResult<()> {
+                        <Self as serde_flow::flow::File<Self>>::save_to_path::<#encoder>(self, path)
+                    }
+                }
+            };
+        }
+
+        if self.is_file && self.is_nonbloking {
+            let (impl_generics, where_clause) = self.split_for_impl(&self.async_bounds());
+            generated = quote! {
+                #generated
+                impl #impl_generics #self_type #where_clause {
+                    /// Loads the object from the file with the encoder of the type, migrating older variants in memory.
+                    pub fn load_async(path: &std::path::Path) -> serde_flow::flow::AsyncResult<'_, Self> {
+                        <Self as serde_flow::flow::FileAsync<Self>>::load_from_path_async::<#encoder>(path)
+                    }
+                    /// Saves the object into the file with the encoder of the type.
+                    pub fn save_async<'a>(&'a self, path: &'a std::path::Path) -> serde_flow::flow::AsyncResult<'a, ()> {
+                        <Self as serde_flow::flow::FileAsync<Self>>::save_to_path_async::<#encoder>(self, path)
+                    }
+                }
+            };
+        }
+
+        if self.is_bytes {
+            let (impl_generics, where_clause) = self.split_for_impl(&self.migrate_bounds());
+            generated = quote! {
+                #generated
+                impl #impl_generics #self_type #where_clause {
+                    /// Encodes the object with the encoder of the type.
+                    pub fn to_bytes(&self) -> serde_flow::flow::FlowResult<Vec<u8>> {
+                        <Self as serde_flow::flow::Bytes<Self>>::encode::<#encoder>(self)
+                    }
+                    /// Decodes the object with the encoder of the type, migrating older variants.
+                    pub fn from_bytes(bytes: &[u8]) -> serde_flow::flow::FlowResult<Self> {
+                        <Self as serde_flow::flow::Bytes<Self>>::decode::<#encoder>(bytes)
+                    }
+                }
+            };
+        }
+        generated
+    }
+
+    /// Resolves `#[flow(encoder = ..)]`, `bincode` and `json` name the encoders shipped with serde_flow.
+    fn encoder_type(&self) -> Option<proc_macro2::TokenStream> {
+        let encoder = self.encoder.as_ref()?;
+        if encoder.is_ident("bincode") {
+            return Some(quote! { serde_flow::encoder::bincode::Encoder });
+        }
+        if encoder.is_ident("json") {
+            return Some(quote! { serde_flow::encoder::json::Encoder });
+        }
+        Some(quote! { #encoder })
+    }
+
     fn generate_zerocopy_file(
         &self,
         previous: proc_macro2::TokenStream,
     ) -> proc_macro2::TokenStream {
-        let struct_name = self.struct_name.clone();
+        let self_type = self.self_type();
+        let (impl_generics, where_clause) = self.split_for_impl(&self.migrate_bounds());
+        let (async_impl_generics, async_where_clause) = self.split_for_impl(&self.async_bounds());
 
         let mut generated = previous;
         if self.is_bloking {
@@ -146,7 +437,7 @@ impl FlowGenerator {
             let func_save_to_path = self.component_save_to_path(self.is_zerocopy, true);
             generated = quote! {
                 #generated
-                impl serde_flow::flow::zerocopy::File<#struct_name> for #struct_name {
+                impl #impl_generics serde_flow::flow::zerocopy::File<#self_type> for #self_type #where_clause {
                     #func_load_from_path
                     #func_save_to_path
                 }
@@ -158,7 +449,7 @@ impl FlowGenerator {
             let func_save_to_path = self.component_save_to_path(self.is_zerocopy, false);
             generated = quote! {
                 #generated
-                impl serde_flow::flow::zerocopy::FileAsync<#struct_name> for #struct_name {
+                impl #async_impl_generics serde_flow::flow::zerocopy::FileAsync<#self_type> for #self_type #async_where_clause {
                     #func_load_from_path
                     #func_save_to_path
                 }
@@ -170,18 +461,21 @@ impl FlowGenerator {
         }
 
         if self.is_bloking {
+            let mut bounds = self.migrate_bounds();
+            bounds.push(quote! { #self_type: serde_flow::flow::zerocopy::File<#self_type> });
+            let (impl_generics, where_clause) = self.split_for_impl(&bounds);
             generated = quote! {
                 #generated
-                impl serde_flow::flow::zerocopy::FileMigrate<#struct_name> for #struct_name {
+                impl #impl_generics serde_flow::flow::zerocopy::FileMigrate<#self_type> for #self_type #where_clause {
                     fn load_and_migrate(path: &std::path::Path)
-                        -> serde_flow::flow::FlowResult<serde_flow::encoder::zerocopy::Reader<#struct_name>>
+                        -> serde_flow::flow::FlowResult<serde_flow::encoder::zerocopy::Reader<#self_type>>
                     {
                         use serde_flow::flow::zerocopy::File;
-                        #struct_name::load_from_path(path)
+                        Self::load_from_path(path)
                     }
                     fn migrate(path: &std::path::Path) -> serde_flow::flow::FlowResult<()> {
                         use serde_flow::flow::zerocopy::File;
-                        let _ = #struct_name::load_from_path(path)?;
+                        let _ = Self::load_from_path(path)?;
                         Ok(())
                     }
                 }
@@ -189,19 +483,22 @@ impl FlowGenerator {
         }
 
         if self.is_nonbloking {
+            let mut bounds = self.async_bounds();
+            bounds.push(quote! { #self_type: serde_flow::flow::zerocopy::FileAsync<#self_type> });
+            let (impl_generics, where_clause) = self.split_for_impl(&bounds);
             generated = quote! {
                 #generated
-                impl serde_flow::flow::zerocopy::FileMigrateAsync<#struct_name> for #struct_name {
+                impl #impl_generics serde_flow::flow::zerocopy::FileMigrateAsync<#self_type> for #self_type #where_clause {
                     fn load_and_migrate_async(path: &std::path::Path)
-                        -> serde_flow::flow::AsyncResult<serde_flow::encoder::zerocopy::Reader<#struct_name>>
+                        -> serde_flow::flow::AsyncResult<serde_flow::encoder::zerocopy::Reader<#self_type>>
                     {
                         use serde_flow::flow::zerocopy::FileAsync;
-                        #struct_name::load_from_path_async(path)
+                        Self::load_from_path_async(path)
                     }
                     fn migrate_async(path: &std::path::Path) -> serde_flow::flow::AsyncResult<()> {
                         std::boxed::Box::pin(async {
                             use serde_flow::flow::zerocopy::FileAsync;
-                            let _ = #struct_name::load_from_path_async(path).await?;
+                            let _ = Self::load_from_path_async(path).await?;
                             Ok(())
                         })
                     }
@@ -212,7 +509,9 @@ impl FlowGenerator {
     }
 
     fn generate_normal_file(&self, previous: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
-        let struct_name = self.struct_name.clone();
+        let self_type = self.self_type();
+        let (impl_generics, where_clause) = self.split_for_impl(&self.migrate_bounds());
+        let (async_impl_generics, async_where_clause) = self.split_for_impl(&self.async_bounds());
 
         let mut generated = previous;
         if self.is_bloking {
@@ -220,7 +519,7 @@ impl FlowGenerator {
             let func_save_to_path = self.component_save_to_path(self.is_zerocopy, true);
             generated = quote! {
                 #generated
-                impl serde_flow::flow::File<#struct_name> for #struct_name {
+                impl #impl_generics serde_flow::flow::File<#self_type> for #self_type #where_clause {
                     #func_load_from_path
                     #func_save_to_path
                 }
@@ -232,7 +531,7 @@ impl FlowGenerator {
             let func_save_to_path = self.component_save_to_path(self.is_zerocopy, false);
             generated = quote! {
                 #generated
-                impl serde_flow::flow::FileAsync<#struct_name> for #struct_name {
+                impl #async_impl_generics serde_flow::flow::FileAsync<#self_type> for #self_type #async_where_clause {
                     #func_load_from_path
                     #func_save_to_path
                 }
@@ -244,44 +543,149 @@ impl FlowGenerator {
         }
 
         // Migrations
+        let flow_id = quote! { <Self as serde_flow::FlowInfo>::VARIANT };
         if self.is_bloking {
+            let file_read = Self::component_fs_read(true);
+            let backup = self.component_backup(true);
+            let load_and_migrate = Self::component_traced(
+                quote! { Migrate },
+                true,
+                quote! { path },
+                quote! {
+                    use serde_flow::flow::File;
+                    if !path.exists() {
+                        return Err(serde_flow::error::SerdeFlowError::FileNotFound);
+                    }
+
+                    #file_read
+                    let object = serde_flow::flow::decode::<Self, E>(&bytes)?;
+                    let variant = serde_flow::flow::stored_variant::<E>(&bytes)?;
+                    if variant != #flow_id || serde_flow::flow::header::is_legacy(&bytes) {
+                        #backup
+                        object.save_to_path::<E>(path)?;
+                    }
+                    Ok(object)
+                },
+            );
+            let migrate_encoding = Self::component_traced(
+                quote! { Migrate },
+                true,
+                quote! { path },
+                quote! {
+                    use serde_flow::flow::File;
+                    if !path.exists() {
+                        return Err(serde_flow::error::SerdeFlowError::FileNotFound);
+                    }
+
+                    #file_read
+                    // the file was already migrated to the encoder `D`
+                    if !serde_flow::flow::header::is_legacy(&bytes) {
+                        let header = serde_flow::flow::header::Header::parse(&bytes)?;
+                        if header.encoder == D::ID && header.variant == #flow_id {
+                            serde_flow::flow::decode::<Self, D>(&bytes)?;
+                            return Ok(());
+                        }
+                    }
+                    let object = serde_flow::flow::decode::<Self, S>(&bytes)?;
+                    let variant = serde_flow::flow::stored_variant::<S>(&bytes)?;
+                    if variant != #flow_id || serde_flow::flow::header::is_legacy(&bytes) || S::ID != D::ID {
+                        #backup
+                        object.save_to_path::<D>(path)?;
+                    }
+                    Ok(())
+                },
+            );
+            let mut bounds = self.migrate_bounds();
+            bounds.push(quote! { #self_type: serde_flow::flow::File<#self_type> });
+            let (impl_generics, where_clause) = self.split_for_impl(&bounds);
             generated = quote! {
                 #generated
-                impl serde_flow::flow::FileMigrate<#struct_name> for #struct_name {
-                    fn load_and_migrate<E: serde_flow::encoder::FlowEncoder>(path: &std::path::Path) -> serde_flow::flow::FlowResult<#struct_name> {
-                        use serde_flow::flow::File;
-                        let object = #struct_name::load_from_path::<E>(path)?;
-                        object.save_to_path::<E>(path)?;
-                        Ok(object)
+                impl #impl_generics serde_flow::flow::FileMigrate<#self_type> for #self_type #where_clause {
+                    fn load_and_migrate<E: serde_flow::encoder::FlowEncoder>(path: &std::path::Path) -> serde_flow::flow::FlowResult<#self_type> {
+                        #load_and_migrate
                     }
                     fn migrate<E: serde_flow::encoder::FlowEncoder>(path: &std::path::Path) -> serde_flow::flow::FlowResult<()> {
-                        use serde_flow::flow::File;
-                        let object = #struct_name::load_from_path::<E>(path)?;
-                        object.save_to_path::<E>(path)
+                        Self::load_and_migrate::<E>(path).map(|_| ())
+                    }
+                    fn migrate_encoding<S: serde_flow::encoder::FlowEncoder, D: serde_flow::encoder::FlowEncoder>(
+                        path: &std::path::Path,
+                    ) -> serde_flow::flow::FlowResult<()> {
+                        #migrate_encoding
                     }
                 }
             };
         }
 
         if self.is_nonbloking {
+            let file_read = Self::component_fs_read(false);
+            let backup = self.component_backup(false);
+            let load_and_migrate = Self::component_traced(
+                quote! { Migrate },
+                false,
+                quote! { path },
+                quote! {
+                    use serde_flow::flow::FileAsync;
+                    if !path.exists() {
+                        return Err(serde_flow::error::SerdeFlowError::FileNotFound);
+                    }
+
+                    #file_read
+                    let object = serde_flow::flow::decode::<Self, E>(&bytes)?;
+                    let variant = serde_flow::flow::stored_variant::<E>(&bytes)?;
+                    if variant != #flow_id || serde_flow::flow::header::is_legacy(&bytes) {
+                        #backup
+                        object.save_to_path_async::<E>(path).await?;
+                    }
+                    Ok(object)
+                },
+            );
+            let migrate_encoding = Self::component_traced(
+                quote! { Migrate },
+                false,
+                quote! { path },
+                quote! {
+                    use serde_flow::flow::FileAsync;
+                    if !path.exists() {
+                        return Err(serde_flow::error::SerdeFlowError::FileNotFound);
+                    }
+
+                    #file_read
+                    // the file was already migrated to the encoder `D`
+                    if !serde_flow::flow::header::is_legacy(&bytes) {
+                        let header = serde_flow::flow::header::Header::parse(&bytes)?;
+                        if header.encoder == D::ID && header.variant == #flow_id {
+                            serde_flow::flow::decode::<Self, D>(&bytes)?;
+                            return Ok(());
+                        }
+                    }
+                    let object = serde_flow::flow::decode::<Self, S>(&bytes)?;
+                    let variant = serde_flow::flow::stored_variant::<S>(&bytes)?;
+                    if variant != #flow_id || serde_flow::flow::header::is_legacy(&bytes) || S::ID != D::ID {
+                        #backup
+                        object.save_to_path_async::<D>(path).await?;
+                    }
+                    Ok(())
+                },
+            );
+            let mut bounds = self.async_bounds();
+            bounds.push(quote! { #self_type: serde_flow::flow::FileAsync<#self_type> });
+            let (impl_generics, where_clause) = self.split_for_impl(&bounds);
             generated = quote! {
                 #generated
-                impl serde_flow::flow::FileMigrateAsync<#struct_name> for #struct_name {
-                    fn load_and_migrate_async<E: serde_flow::encoder::FlowEncoder>(path: &std::path::Path) -> serde_flow::flow::AsyncResult<#struct_name> {
-                        std::boxed::Box::pin(async {
-                            use serde_flow::flow::FileAsync;
-                            let object = #struct_name::load_from_path_async::<E>(path).await?;
-                            object.save_to_path_async::<E>(path).await?;
-                            Ok(object)
-                        })
+                impl #impl_generics serde_flow::flow::FileMigrateAsync<#self_type> for #self_type #where_clause {
+                    fn load_and_migrate_async<E: serde_flow::encoder::FlowEncoder>(path: &std::path::Path) -> serde_flow::flow::AsyncResult<#self_type> {
+                        #load_and_migrate
                     }
                     fn migrate_async<E: serde_flow::encoder::FlowEncoder>(path: &std::path::Path) -> serde_flow::flow::AsyncResult<()> {
-                        std::boxed::Box::pin(async {
-                            use serde_flow::flow::FileAsync;
-                            let object = #struct_name::load_from_path_async::<E>(path).await?;
-                            object.save_to_path_async::<E>(path).await
+                        std::boxed::Box::pin(async move {
+                            Self::load_and_migrate_async::<E>(path).await.map(|_| ())
                         })
                     }
+                    fn migrate_encoding_async<S: serde_flow::encoder::FlowEncoder, D: serde_flow::encoder::FlowEncoder>(
+                        path: &std::path::Path,
+                    ) -> serde_flow::flow::AsyncResult<()> {
+                        #migrate_encoding
+                    }
                 }
             };
         }
@@ -293,55 +697,70 @@ impl FlowGenerator {
         is_zerocopy: bool,
         is_bloking: bool,
     ) -> proc_macro2::TokenStream {
-        let struct_name = self.struct_name.clone();
+        let self_type = self.self_type();
         let file_read = Self::component_fs_read(is_bloking);
         let decode_with_version = self.decode_with_version();
 
         // NON zerocopy
         if !is_zerocopy {
-            let func_body = quote! {
-                if !path.exists() {
-                    return Err(serde_flow::error::SerdeFlowError::FileNotFound);
-                }
+            let func_body = Self::component_traced(
+                quote! { Load },
+                is_bloking,
+                quote! { path },
+                quote! {
+                    if !path.exists() {
+                        return Err(serde_flow::error::SerdeFlowError::FileNotFound);
+                    }
 
-                #file_read
-                #decode_with_version
-            };
+                    #file_read
+                    #decode_with_version
+                },
+            );
             if is_bloking {
                 return quote! {
-                    fn load_from_path<E: serde_flow::encoder::FlowEncoder>(path: &std::path::Path) -> serde_flow::flow::FlowResult<#struct_name> {
+                    fn load_from_path_with<C: serde_flow::encoder::FlowEncoderInstance>(encoder: &C, path: &std::path::Path) -> serde_flow::flow::FlowResult<#self_type> {
                         #func_body
                     }
                 };
             }
             return quote! {
-                fn load_from_path_async<E: serde_flow::encoder::FlowEncoder>(path: &std::path::Path) -> serde_flow::flow::AsyncResult<#struct_name> {
-                    std::boxed::Box::pin(async move { #func_body })
+                fn load_from_path_async_with<'a, C: serde_flow::encoder::FlowEncoderInstance + Sync>(encoder: &'a C, path: &'a std::path::Path) -> serde_flow::flow::AsyncResult<'a, #self_type> {
+                    #func_body
                 }
             };
         }
 
         // zerocopy
+        let save_migrated = self.component_save_migrated(is_bloking);
         let func_body = quote! {
             if !path.exists() {
                 return Err(serde_flow::error::SerdeFlowError::FileNotFound);
             }
             #file_read
             #decode_with_version
+            #save_migrated
         };
         if is_bloking {
+            let func_body =
+                Self::component_traced(quote! { Load }, true, quote! { path }, func_body);
             quote! {
-                fn load_from_path(path: &std::path::Path) -> serde_flow::flow::FlowResult<serde_flow::encoder::zerocopy::Reader<#struct_name>> {
+                fn load_from_path(path: &std::path::Path) -> serde_flow::flow::FlowResult<serde_flow::encoder::zerocopy::Reader<#self_type>> {
                     #func_body
                 }
             }
         } else {
+            let func_body = Self::component_traced(
+                quote! { Load },
+                false,
+                quote! { path_to.as_path() },
+                quote! {
+                    let path = path_to.as_path();
+                    #func_body
+                },
+            );
             quote! {
-                fn load_from_path_async<'a>(path_to: std::path::PathBuf) -> serde_flow::flow::AsyncResult<'a, serde_flow::encoder::zerocopy::Reader<'a, #struct_name>> {
-                    std::boxed::Box::pin(async move {
-                        let path = path_to.as_path();
-                        #func_body
-                    })
+                fn load_from_path_async(path_to: std::path::PathBuf) -> serde_flow::flow::AsyncResult<'static, serde_flow::encoder::zerocopy::Reader<#self_type>> {
+                    #func_body
                 }
             }
         }
@@ -361,160 +780,227 @@ impl FlowGenerator {
         };
 
         if !is_zerocopy {
+            let func_body =
+                Self::component_traced(quote! { Save }, is_bloking, quote! { path }, func_body);
             if is_bloking {
                 return quote! {
-                    fn save_to_path<E: serde_flow::encoder::FlowEncoder>(&self, path: &std::path::Path) -> serde_flow::flow::FlowResult<()> {
+                    fn save_to_path_with<C: serde_flow::encoder::FlowEncoderInstance>(&self, encoder: &C, path: &std::path::Path) -> serde_flow::flow::FlowResult<()> {
                         #func_body
                     }
                 };
             }
 
             return quote! {
-                fn save_to_path_async<'a, E: serde_flow::encoder::FlowEncoder>(&'a self, path: &'a std::path::Path) -> serde_flow::flow::AsyncResult<()> {
-                    std::boxed::Box::pin(async move { #func_body })
+                fn save_to_path_async_with<'a, C: serde_flow::encoder::FlowEncoderInstance + Sync>(&'a self, encoder: &'a C, path: &'a std::path::Path) -> serde_flow::flow::AsyncResult<'a, ()> {
+                    #func_body
                 }
             };
         }
 
         if is_bloking {
+            let func_body =
+                Self::component_traced(quote! { Save }, true, quote! { path }, func_body);
             quote! {
                 fn save_to_path(&self, path: &std::path::Path) -> serde_flow::flow::FlowResult<()> {
                     #func_body
                 }
             }
         } else {
+            let func_body = Self::component_traced(
+                quote! { Save },
+                false,
+                quote! { path_to.as_path() },
+                quote! {
+                    let path = path_to.as_path();
+                    #func_body
+                },
+            );
             quote! {
                 fn save_to_path_async(&self, path_to: std::path::PathBuf) -> serde_flow::flow::AsyncResult<()> {
-                    std::boxed::Box::pin(async move {
-                        let path = path_to.as_path();
-                        #func_body
-                    })
+                    #func_body
                 }
             }
         }
     }
 
     fn encode_with_version(&self) -> proc_macro2::TokenStream {
-        let struct_name = self.struct_name.clone();
-        let current_variant = self.variant;
+        let current_flow_id = quote! { <Self as serde_flow::FlowInfo>::VARIANT };
         if self.is_zerocopy {
             return quote! {
-                let mut total_bytes = #current_variant.to_le_bytes().to_vec();
-                let bytes = serde_flow::encoder::zerocopy::Encoder::serialize::<#struct_name>(self)?;
-                total_bytes.extend_from_slice(&bytes);
+                let started = serde_flow::trace::start();
+                let bytes = serde_flow::encoder::zerocopy::Encoder::serialize::<Self>(self)?;
+                let total_bytes = serde_flow::flow::header::Header::new(serde_flow::encoder::zerocopy::Encoder::ID, #current_flow_id)
+                    .with_payload(&bytes);
+                serde_flow::trace::encoded(
+                    std::any::type_name::<Self>(),
+                    serde_flow::encoder::zerocopy::Encoder::ID,
+                    #current_flow_id,
+                    total_bytes.len(),
+                    started,
+                );
             };
         }
 
         // Normal - NON ZeroCopy
-        let current_flow_id = gen_variant_id_name(&struct_name);
-        let current_dto_name = gen_variant_dto_name(&struct_name);
         quote! {
-            let mut flow_object = #current_dto_name::new(#current_flow_id, self);
-            let total_bytes = E::serialize::<#current_dto_name>(&flow_object)?;
+            let total_bytes = serde_flow::flow::encode_with(encoder, self, #current_flow_id)?;
         }
     }
 
     fn decode_with_version(&self) -> proc_macro2::TokenStream {
-        let struct_name = self.struct_name.clone();
-        let current_variant = self.variant;
-
         if self.is_zerocopy {
-            let variants = self.variants.clone().unwrap_or_default();
-            let variants: Vec<proc_macro2::TokenStream> = variants
-                .into_iter()
-                .map(|i| {
-                    let const_variant_id_name = gen_variant_id_name(&i);
-                    quote! {
-                        #const_variant_id_name => {
-                            use serde_flow::flow::zerocopy::File;
-                            let old_object = serde_flow::encoder::zerocopy::Reader::<#i>::new(bytes).deserialize()?;
-                            let converted = #struct_name::from(old_object);
-                            converted.save_to_path(path)?;
-                            #struct_name::load_from_path(path)
-                        },
-                    }
-                })
-                .collect();
-
+            let flow_id = quote! { <Self as serde_flow::FlowInfo>::VARIANT };
             return quote! {
-                if bytes.len() < 2 {
-                    return Err(serde_flow::error::SerdeFlowError::FormatInvalid);
-                }
-                // Extract the first two bytes and convert them to a u16 in little-endian format
-                let flow_id = u16::from_le_bytes([bytes[0], bytes[1]]);
-
-                // Remove the first two bytes from the original Vec<u8>
-                let bytes = bytes.split_off(2);
-                match flow_id {
-                    #current_variant => Ok(serde_flow::encoder::zerocopy::Reader::<#struct_name>::new(bytes)),
-                    #(#variants)*
-                    _ => Err(serde_flow::error::SerdeFlowError::VariantNotFound),
-                }
+                let started = serde_flow::trace::start();
+                let migrated = if serde_flow::flow::header::is_legacy(&bytes) {
+                    // even the current variant is deserialized, so it's written again with the header
+                    let (variant, payload) = serde_flow::flow::zerocopy::legacy_payload(&bytes)?;
+                    serde_flow::trace::decoded(
+                        std::any::type_name::<Self>(),
+                        serde_flow::encoder::zerocopy::Encoder::ID,
+                        variant,
+                        bytes.len(),
+                        started,
+                    );
+                    <Self as serde_flow::flow::zerocopy::Migrate>::deserialize_variant(variant, payload)?
+                } else {
+                    let header = serde_flow::flow::header::Header::parse(&bytes)?;
+                    header.verify(&bytes[serde_flow::flow::header::HEADER_SIZE..])?;
+                    serde_flow::trace::decoded(
+                        std::any::type_name::<Self>(),
+                        header.encoder,
+                        header.variant,
+                        bytes.len(),
+                        started,
+                    );
+
+                    let payload = &bytes[serde_flow::flow::header::HEADER_SIZE..];
+                    if header.encoder == serde_flow::encoder::zerocopy::Encoder::ID {
+                        // the payload is copied into an aligned buffer, the header is dropped with the original bytes
+                        if header.variant == #flow_id {
+                            return serde_flow::encoder::zerocopy::Reader::<Self>::from_slice(payload);
+                        }
+                        <Self as serde_flow::flow::zerocopy::Migrate>::deserialize_variant(header.variant, payload)?
+                    } else {
+                        // serde variants listed with `#[variants(serde(..))]`
+                        <Self as serde_flow::flow::zerocopy::Migrate>::deserialize_foreign(&header, payload)?
+                    }
+                };
             };
         }
 
         // Normal - NON ZeroCopy
-        let current_dto_name = gen_variant_dto_name(&struct_name);
-        let variants: Vec<proc_macro2::TokenStream> = self
-            .variants
-            .clone()
-            .unwrap_or_default()
-            .into_iter()
-            .map(|variant| {
-                let const_flow_id_name = gen_variant_id_name(&variant);
-                let variant_dto_name = gen_variant_dto_name(&variant);
-                quote! {
-                    #const_flow_id_name => E::deserialize::<#variant_dto_name>(&bytes).map(#variant::from).map(#struct_name::from),
-                }
-            })
-            .collect();
         quote! {
-            if bytes.len() < 2 {
-                return Err(serde_flow::error::SerdeFlowError::FormatInvalid);
-            }
-            let flow_id_object = E::deserialize::<serde_flow::flow::FlowId>(&bytes)?;
-            match flow_id_object.flow_id {
-                #current_variant => E::deserialize::<#current_dto_name>(&bytes).map(#struct_name::from),
-                #(#variants)*
-                _ => Err(serde_flow::error::SerdeFlowError::VariantNotFound),
-            }
+            serde_flow::flow::decode_with::<Self, _>(encoder, &bytes)
         }
     }
 
-    fn component_fs_read(is_bloking: bool) -> proc_macro2::TokenStream {
+    fn component_save_migrated(&self, is_bloking: bool) -> proc_macro2::TokenStream {
+        let mut backup = self.component_backup(is_bloking);
+        if self.is_backup {
+            backup = quote! {
+                let variant = serde_flow::flow::zerocopy::stored_variant(&bytes)?;
+                #backup
+            };
+        }
         if is_bloking {
             return quote! {
-                let mut bytes = std::fs::read(path)?;
+                use serde_flow::flow::zerocopy::File;
+                #backup
+                migrated.save_to_path(path)?;
+                Self::load_from_path(path)
             };
         }
 
-        #[cfg(feature = "async-std")]
-        {
+        quote! {
+            use serde_flow::flow::zerocopy::FileAsync;
+            #backup
+            migrated.save_to_path_async(path.to_path_buf()).await?;
+            Self::load_from_path_async(path.to_path_buf()).await
+        }
+    }
+
+    /// Runs the body of the operation on the file at `path` inside its span, see `serde_flow::trace`,
+    /// and adds the path to its errors. The blocking body is run in a closure, the async one is returned as a boxed future.
+    fn component_traced(
+        operation: proc_macro2::TokenStream,
+        is_bloking: bool,
+        path: proc_macro2::TokenStream,
+        body: proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
+        let span = quote! {
+            serde_flow::trace::file_span(serde_flow::trace::Operation::#operation, std::any::type_name::<Self>(), #path)
+        };
+        if is_bloking {
             return quote! {
-                let mut bytes = async_std::fs::read(path).await?;
+                #span.in_scope(|| { #body }).map_err(|error: serde_flow::error::SerdeFlowError| error.at_path(#path))
             };
         }
 
         quote! {
-            let mut bytes = tokio::fs::read(path).await?;
+            let span = #span;
+            std::boxed::Box::pin(serde_flow::trace::instrument(span, async move {
+                let result: serde_flow::flow::FlowResult<_> = async { #body }.await;
+                result.map_err(|error| error.at_path(#path))
+            }))
+        }
+    }
+
+    fn component_fs_read(is_bloking: bool) -> proc_macro2::TokenStream {
+        if is_bloking {
+            return quote! {
+                let bytes = std::fs::read(path)?;
+            };
+        }
+
+        #[cfg(feature = "async-std")]
+        return quote! {
+            let bytes = async_std::fs::read(path).await?;
+        };
+
+        #[cfg(not(feature = "async-std"))]
+        quote! {
+            let bytes = tokio::fs::read(path).await?;
         }
     }
 
     fn component_fs_write(is_bloking: bool) -> proc_macro2::TokenStream {
         if is_bloking {
             return quote! {
-                std::fs::write(path, &total_bytes)?;
+                serde_flow::fs::write_atomic(path, &total_bytes)?;
             };
         }
 
         #[cfg(feature = "async-std")]
-        {
+        return quote! {
+            serde_flow::fs::write_atomic_async_std(path, &total_bytes).await?;
+        };
+
+        #[cfg(not(feature = "async-std"))]
+        quote! {
+            serde_flow::fs::write_atomic_tokio(path, &total_bytes).await?;
+        }
+    }
+
+    fn component_backup(&self, is_bloking: bool) -> proc_macro2::TokenStream {
+        if !self.is_backup {
+            return quote! {};
+        }
+
+        if is_bloking {
             return quote! {
-                async_std::fs::write(path, &total_bytes).await?;
+                serde_flow::fs::backup(path, variant)?;
             };
         }
+
+        #[cfg(feature = "async-std")]
+        return quote! {
+            serde_flow::fs::backup_async_std(path, variant).await?;
+        };
+
+        #[cfg(not(feature = "async-std"))]
         quote! {
-            tokio::fs::write(path, &total_bytes).await?;
+            serde_flow::fs::backup_tokio(path, variant).await?;
         }
     }
 
@@ -551,18 +1037,31 @@ impl FlowGenerator {
             .iter()
             .find(|attr| attr.path().is_ident("flow"))
             .ok_or(syn::parse::Error::new(
-                input.span(),
-                "flow attribute must be provided",
+                input.ident.span(),
+                "flow attribute must be provided, e.g. `#[flow(variant = 1)]`",
             ))?;
         let variants_attr = attrs.iter().find(|attr| attr.path().is_ident("variants"));
 
-        // parse structure's fields
-        let fields_gen = FieldsGenerator::parse(&input);
+        // only structs and enums are supported
+        if let syn::Data::Union(data) = &input.data {
+            return Err(syn::parse::Error::new(
+                data.union_token.span(),
+                "This macro only supports structs and enums",
+            ));
+        }
+
         // create the flow generator
-        let mut flow_gen = FlowGenerator::new(input.ident.clone(), fields_gen);
+        let mut flow_gen = FlowGenerator::new(input.ident.clone());
+        flow_gen.generics = input.generics.clone();
 
         // parse #flow attribute
         flow_gen.parse_flow(flow_attr)?;
+        if let (true, Some(encoder)) = (flow_gen.is_zerocopy, &flow_gen.encoder) {
+            return Err(syn::parse::Error::new(
+                encoder.span(),
+                "zerocopy types are always encoded with rkyv, `encoder` can't be combined with `zerocopy`",
+            ));
+        }
 
         // parse #variants attribute
         if let Some(variants_attr) = variants_attr {
@@ -572,7 +1071,7 @@ impl FlowGenerator {
         Ok(flow_gen)
     }
 
-    fn new(value: Ident, fields_gen: FieldsGenerator) -> Self {
+    fn new(value: Ident) -> Self {
         Self {
             struct_name: value,
             variant: 1,
@@ -582,129 +1081,241 @@ impl FlowGenerator {
             is_bloking: false,
             is_zerocopy: false,
             is_verify_write: false,
+            is_backup: false,
+            encoder: None,
+            generics: syn::Generics::default(),
             variants: None,
-            fields_gen,
+            foreign_variants: Vec::new(),
         }
     }
 
     fn parse_flow(&mut self, attr: &syn::Attribute) -> syn::parse::Result<()> {
-        match &attr.meta {
-            syn::Meta::List(meta_list) => meta_list.parse_nested_meta(|meta| {
-                if meta.path.is_ident("variant") {
-                    let value = meta.value().unwrap(); // this parses the `=`
-                    let lit: syn::LitInt = value.parse().unwrap();
-                    self.variant = lit.base10_parse::<u16>()?;
-                    return Ok(());
-                }
+        let syn::Meta::List(meta_list) = &attr.meta else {
+            return Err(syn::parse::Error::new(
+                attr.span(),
+                "Failed to parse flow attribute, expected `#[flow(variant = N, ..)]`",
+            ));
+        };
 
-                if meta.path.is_ident("bytes") {
-                    self.is_bytes = true;
-                    return Ok(());
+        let mut seen: Vec<String> = Vec::new();
+        let mut check_duplicate = |meta: &syn::meta::ParseNestedMeta| {
+            let name = meta.path.get_ident().map(ToString::to_string);
+            match name {
+                Some(name) if seen.contains(&name) => {
+                    Err(meta.error(format!("duplicate property `{name}`")))
                 }
-                if meta.path.is_ident("zerocopy") {
-                    self.is_zerocopy = true;
-                    return Ok(());
+                Some(name) => {
+                    seen.push(name);
+                    Ok(())
                 }
+                None => Ok(()),
+            }
+        };
 
-                if meta.path.is_ident("file") {
-                    self.is_file = true;
-                    if meta.input.peek(syn::token::Paren) {
-                        meta.parse_nested_meta(|file_meta| {
-                            if file_meta.path.is_ident("blocking") {
-                                self.is_nonbloking = true;
-                                return Ok(());
-                            }
-                            if file_meta.path.is_ident("nonblocking") {
-                                self.is_nonbloking = true;
-                                return Ok(());
-                            }
-                            if file_meta.path.is_ident("verify_write") {
-                                self.is_verify_write = true;
-                                return Ok(());
-                            }
-                            Err(file_meta.error("unsupported file property"))
-                        })?;
-                    }
-                    // set by default blocking IO
-                    if !self.is_bloking && !self.is_nonbloking {
-                        self.is_bloking = true;
-                    }
+        meta_list.parse_nested_meta(|meta| {
+            check_duplicate(&meta)?;
+            if meta.path.is_ident("variant") {
+                let value = meta.value()?; // this parses the `=`
+                let lit: syn::LitInt = value.parse()?;
+                self.variant = lit.base10_parse::<u16>()?;
+                return Ok(());
+            }
 
-                    return Ok(());
+            if meta.path.is_ident("bytes") {
+                self.is_bytes = true;
+                return Ok(());
+            }
+            if meta.path.is_ident("encoder") {
+                let value = meta.value()?; // this parses the `=`
+                self.encoder = Some(value.parse()?);
+                return Ok(());
+            }
+            if meta.path.is_ident("zerocopy") {
+                self.is_zerocopy = true;
+                return Ok(());
+            }
+
+            if meta.path.is_ident("file") {
+                self.is_file = true;
+                if meta.input.peek(syn::token::Paren) {
+                    meta.parse_nested_meta(|file_meta| {
+                        check_duplicate(&file_meta)?;
+                        if file_meta.path.is_ident("blocking") {
+                            self.is_bloking = true;
+                            return Ok(());
+                        }
+                        if file_meta.path.is_ident("nonblocking") {
+                            self.is_nonbloking = true;
+                            return Ok(());
+                        }
+                        if file_meta.path.is_ident("verify_write") {
+                            self.is_verify_write = true;
+                            return Ok(());
+                        }
+                        if file_meta.path.is_ident("backup") {
+                            self.is_backup = true;
+                            return Ok(());
+                        }
+                        Err(file_meta.error(
+                            "unsupported file property, expected one of `blocking`, `nonblocking`, `verify_write`, `backup`",
+                        ))
+                    })?;
+                }
+                // set by default blocking IO
+                if !self.is_bloking && !self.is_nonbloking {
+                    self.is_bloking = true;
                 }
 
-                Err(meta.error("unsupported flow property"))
-            }),
-            _ => Err(syn::parse::Error::new(
-                attr.span(),
-                "Failed to parse flow attribute",
-            )),
-        }
+                return Ok(());
+            }
+
+            Err(meta.error(
+                "unsupported flow property, expected one of `variant`, `file`, `bytes`, `zerocopy`, `encoder`",
+            ))
+        })
     }
 
     fn parse_variants(&mut self, attr: &syn::Attribute) -> syn::parse::Result<()> {
-        let mut variants = Vec::new();
-        let result = match &attr.meta {
-            syn::Meta::List(meta_list) => meta_list.parse_nested_meta(|meta| {
-                if let Some(ident) = meta.path.get_ident().cloned() {
-                    variants.push(ident);
-                    return Ok(());
-                }
-                Err(meta.error("unsupported variants property"))
-            }),
-            _ => Err(syn::parse::Error::new(
+        let syn::Meta::List(meta_list) = &attr.meta else {
+            return Err(syn::parse::Error::new(
                 attr.span(),
-                "Failed to parse vartiants",
-            )),
+                "Failed to parse vartiants, expected `#[variants(StructA, StructB, ..)]`",
+            ));
+        };
+        let (family, other_family) = if self.is_zerocopy {
+            ("zerocopy", "serde")
+        } else {
+            ("serde", "zerocopy")
         };
-        if result.is_ok() {
-            self.variants = Some(variants);
+
+        let mut variants: Vec<syn::Type> = Vec::new();
+        let mut foreign_variants: Vec<syn::Type> = Vec::new();
+        meta_list.parse_args_with(|input: syn::parse::ParseStream| {
+            while !input.is_empty() {
+                // `serde(StructA, ..)` or `zerocopy(StructA, ..)` lists variants of the other encoding family
+                if input.peek(Ident) && input.peek2(syn::token::Paren) {
+                    let name: Ident = input.parse()?;
+                    let content;
+                    syn::parenthesized!(content in input);
+                    if name == family {
+                        return Err(syn::parse::Error::new(
+                            name.span(),
+                            format!("variants of {family} types are listed without `{family}(..)`"),
+                        ));
+                    }
+                    if name != other_family {
+                        return Err(syn::parse::Error::new(
+                            name.span(),
+                            format!("unsupported encoding family `{name}`, expected `{other_family}(..)`"),
+                        ));
+                    }
+                    let listed = syn::punctuated::Punctuated::<syn::Type, syn::Token![,]>::parse_terminated(&content)?;
+                    foreign_variants.extend(listed);
+                } else {
+                    variants.push(input.parse()?);
+                }
+
+                if input.is_empty() {
+                    break;
+                }
+                input.parse::<syn::Token![,]>()?;
+            }
+            Ok(())
+        })?;
+
+        let mut names: Vec<String> = Vec::new();
+        for variant in variants.iter().chain(&foreign_variants) {
+            let name = type_name(variant);
+            if self.struct_name == name || name == "Self" {
+                return Err(syn::parse::Error::new(
+                    variant.span(),
+                    "a type can't be a variant of itself",
+                ));
+            }
+            if names.contains(&name) {
+                return Err(syn::parse::Error::new(
+                    variant.span(),
+                    format!("variant `{name}` is listed more than once"),
+                ));
+            }
+            names.push(name);
         }
-        result
+        self.variants = Some(variants);
+        self.foreign_variants = foreign_variants;
+        Ok(())
     }
-}
 
-struct FieldsGenerator {
-    fields: syn::FieldsNamed,
-}
+    /// Returns the variants of both encoding families.
+    fn all_variants(&self) -> Vec<syn::Type> {
+        let mut variants = self.variants.clone().unwrap_or_default();
+        variants.extend(self.foreign_variants.iter().cloned());
+        variants
+    }
 
-impl FieldsGenerator {
-    pub fn parse(input: &DeriveInput) -> Self {
-        let fields: syn::FieldsNamed = if let syn::Data::Struct(s) = &input.data {
-            match s.fields.clone() {
-                syn::Fields::Named(fields) => fields,
-                _ => panic!("Unit structs are not supported"),
-            }
-        } else {
-            panic!("This macro only supports structs");
-        };
-        Self { fields }
+    /// Returns the type with its generic parameters, e.g. `Page<T>`.
+    fn self_type(&self) -> proc_macro2::TokenStream {
+        let struct_name = &self.struct_name;
+        let (_, ty_generics, _) = self.generics.split_for_impl();
+        quote! { #struct_name #ty_generics }
     }
 
-    pub fn fields(&self) -> Vec<proc_macro2::TokenStream> {
-        self.fields
-            .named
-            .iter()
-            .map(quote::ToTokens::to_token_stream)
-            .collect()
+    /// Splits the generics for the impl block and extends its where clause with `bounds`.
+    /// Non-generic types get no extra bounds, so the missing implementations are reported on the traits.
+    fn split_for_impl(
+        &self,
+        bounds: &[proc_macro2::TokenStream],
+    ) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+        let (impl_generics, _, where_clause) = self.generics.split_for_impl();
+        if self.generics.params.is_empty() {
+            return (quote! { #impl_generics }, quote! { #where_clause });
+        }
+
+        let mut where_clause = where_clause
+            .cloned()
+            .unwrap_or_else(|| syn::parse_quote!(where));
+        for bound in bounds {
+            where_clause.predicates.push(syn::parse_quote!(#bound));
+        }
+        (quote! { #impl_generics }, quote! { #where_clause })
     }
 
-    pub fn field_names(&self) -> Vec<Ident> {
-        self.fields
-            .named
-            .iter()
-            .map(|f| f.ident.clone().unwrap())
-            .collect()
+    /// Bounds required by the encoding of the type itself.
+    fn encoding_bounds(&self) -> Vec<proc_macro2::TokenStream> {
+        let self_type = self.self_type();
+        if self.is_zerocopy {
+            return vec![
+                quote! { #self_type: rkyv::Archive + rkyv::Serialize<serde_flow::encoder::zerocopy::DefaultSerializer> },
+                quote! {
+                    <#self_type as rkyv::Archive>::Archived: for<'b> rkyv::CheckBytes<rkyv::validation::validators::DefaultValidator<'b>>
+                        + rkyv::Deserialize<#self_type, rkyv::Infallible>
+                },
+            ];
+        }
+        vec![quote! { #self_type: serde::Serialize + serde::de::DeserializeOwned }]
     }
-}
 
-fn gen_variant_id_name(iden: &Ident) -> Ident {
-    Ident::new(
-        &format!("FLOW_ID_{}", iden.to_string().to_uppercase()),
-        proc_macro2::Span::call_site(),
-    )
+    /// Bounds required by the migrations of the type, including the ones of the encoding.
+    fn migrate_bounds(&self) -> Vec<proc_macro2::TokenStream> {
+        let self_type = self.self_type();
+        let mut bounds = self.encoding_bounds();
+        if self.is_zerocopy {
+            bounds.push(quote! { #self_type: serde_flow::flow::zerocopy::Migrate });
+        } else {
+            bounds.push(quote! { #self_type: serde_flow::flow::Migrate });
+        }
+        bounds
+    }
+
+    /// Bounds required by the async implementations, the futures are sent between threads.
+    fn async_bounds(&self) -> Vec<proc_macro2::TokenStream> {
+        let self_type = self.self_type();
+        let mut bounds = self.migrate_bounds();
+        bounds.push(quote! { #self_type: Send + Sync + 'static });
+        bounds
+    }
 }
 
-fn gen_variant_dto_name(ident: &Ident) -> Ident {
-    Ident::new(&format!("{ident}_FlowDto"), proc_macro2::Span::call_site())
+/// Returns the type listed in `#[variants(..)]` as written, e.g. `legacy::User` or `PageV1<T>`.
+fn type_name(ty: &syn::Type) -> String {
+    quote! { #ty }.to_string().replace(' ', "")
 }
@@ -1,15 +1,16 @@
-use std::{fs::OpenOptions, path::Path};
+use std::collections::HashMap;
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use memmap2::MmapMut;
-use serde_flow::{
-    encoder::{bincode, zerocopy::ReaderMemmap, FlowEncoder},
-    error::SerdeFlowError,
-};
+use serde_flow::encoder::zerocopy::MmapReader;
+use serde_flow::encoder::{bincode, json, FlowEncoder};
+use serde_flow::flow::header::Header;
+use serde_flow::flow::Bytes;
+use serde_flow::FlowInfo;
 use tempfile::tempdir;
 
-#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, serde_flow::Flow)]
 #[archive(check_bytes)]
+#[flow(variant = 1, zerocopy)]
 struct PaymentRkyv {
     pub number1: u16,
     pub number2: u64,
@@ -24,6 +25,7 @@ struct PaymentSerde {
 fn bench_encoding(c: &mut Criterion) {
     let temp_dir = tempdir().unwrap();
     let rkyv_path = temp_dir.path().to_path_buf().join("rkyv");
+    let rkyv_mmap_path = temp_dir.path().to_path_buf().join("rkyv_mmap");
     let serde_path = temp_dir.path().to_path_buf().join("serde");
 
     let serde_object = PaymentSerde {
@@ -39,27 +41,25 @@ fn bench_encoding(c: &mut Criterion) {
     let rkyv_bytes = serde_flow::encoder::zerocopy::Encoder::serialize(&rkyv_object).unwrap();
     let serde_bytes = serde_flow::encoder::bincode::Encoder::serialize(&serde_object).unwrap();
 
+    std::fs::write(
+        rkyv_mmap_path.as_path(),
+        Header::new(
+            serde_flow::encoder::zerocopy::Encoder::ID,
+            PaymentRkyv::VARIANT,
+        )
+        .with_payload(&rkyv_bytes),
+    )
+    .unwrap();
     std::fs::write(rkyv_path.as_path(), rkyv_bytes).unwrap();
     std::fs::write(serde_path.as_path(), serde_bytes).unwrap();
 
     let mut group = c.benchmark_group("Serializing");
     group.bench_function("rkyv mmap", |b| {
         b.iter(|| {
-            let file = OpenOptions::new()
-                .read(true)
-                .write(true)
-                .create(true)
-                .open(rkyv_path.as_path())
-                .unwrap();
-
-            let mmap = black_box(unsafe { MmapMut::map_mut(&file).unwrap() });
-            let mut reader_mut = black_box(ReaderMemmap::<PaymentRkyv>::new(mmap));
-            reader_mut
-                .archive_mut(|payment| {
-                    payment.number1 = 2;
-                    payment.number2 = 2;
-                })
-                .unwrap();
+            let file = std::fs::File::open(rkyv_mmap_path.as_path()).unwrap();
+            let mmap = black_box(unsafe { memmap2::Mmap::map(&file).unwrap() });
+            let reader = black_box(MmapReader::<PaymentRkyv>::new(mmap).unwrap());
+            black_box(reader.number2);
         });
     });
 
@@ -85,5 +85,79 @@ fn bench_encoding(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_encoding);
+#[derive(serde::Serialize, serde::Deserialize, serde_flow::Flow)]
+#[flow(variant = 1, bytes)]
+struct Ledger {
+    pub payments: HashMap<String, PaymentSerde>,
+}
+
+/// Layout of the payloads before the header: the variant id was the first field of the payload.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct LegacyLedger {
+    pub flow_id: u16,
+    pub payments: HashMap<String, PaymentSerde>,
+}
+
+#[derive(serde::Deserialize)]
+struct LegacyFlowId {
+    pub flow_id: u16,
+}
+
+fn create_payments() -> HashMap<String, PaymentSerde> {
+    (0..10000u32)
+        .map(|i| {
+            let payment = PaymentSerde {
+                number1: (i % 1000) as u16,
+                number2: u64::from(i) * 7,
+            };
+            (format!("Payment{i}"), payment)
+        })
+        .collect()
+}
+
+/// Reads the variant id first and deserializes the whole payload again, as the payloads without the header did.
+fn decode_two_pass<E: FlowEncoder>(bytes: &[u8]) -> LegacyLedger {
+    let flow_id = E::deserialize::<LegacyFlowId>(bytes).unwrap().flow_id;
+    let ledger = E::deserialize::<LegacyLedger>(bytes).unwrap();
+    assert_eq!(flow_id, ledger.flow_id);
+    ledger
+}
+
+fn bench_decoding(c: &mut Criterion) {
+    let legacy = LegacyLedger {
+        flow_id: 1,
+        payments: create_payments(),
+    };
+    let ledger = Ledger {
+        payments: create_payments(),
+    };
+
+    let legacy_bincode = bincode::Encoder::serialize(&legacy).unwrap();
+    let legacy_json = json::Encoder::serialize(&legacy).unwrap();
+    let ledger_bincode = ledger.encode::<bincode::Encoder>().unwrap();
+    let ledger_json = ledger.encode::<json::Encoder>().unwrap();
+
+    let mut group = c.benchmark_group("Decode");
+    group.bench_function("bincode two-pass", |b| {
+        b.iter(|| {
+            black_box(decode_two_pass::<bincode::Encoder>(black_box(
+                &legacy_bincode,
+            )))
+        });
+    });
+    group.bench_function("bincode single-pass", |b| {
+        b.iter(|| {
+            black_box(Ledger::decode::<bincode::Encoder>(black_box(&ledger_bincode)).unwrap())
+        });
+    });
+    group.bench_function("json two-pass", |b| {
+        b.iter(|| black_box(decode_two_pass::<json::Encoder>(black_box(&legacy_json))));
+    });
+    group.bench_function("json single-pass", |b| {
+        b.iter(|| black_box(Ledger::decode::<json::Encoder>(black_box(&ledger_json)).unwrap()));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_encoding, bench_decoding);
 criterion_main!(benches);
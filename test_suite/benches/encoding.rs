@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde_flow::encoder::{bincode, json, FlowEncoder};
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Object {
+    pub field1: String,
+    pub field2: String,
+    pub values: HashMap<String, Value>,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct Value {
+    pub field: String,
+    pub number1: u32,
+    pub number2: u32,
+}
+
+fn create_object() -> Object {
+    let mut values = HashMap::new();
+    for i in 0..20000 {
+        values.insert(
+            format!("Id{i}"),
+            Value {
+                field: format!("{i}Some value could be long here {i}"),
+                number1: i * 10,
+                number2: i * 20,
+            },
+        );
+    }
+
+    Object {
+        field1: "Some field".to_string(),
+        field2: "Some another field".to_string(),
+        values,
+    }
+}
+
+/// The object with its variant, as `#[derive(Flow)]` generated it before the type was serialized by reference.
+#[derive(serde::Serialize)]
+pub struct ObjectFlowDto {
+    pub flow_id: u16,
+    pub field1: String,
+    pub field2: String,
+    pub values: HashMap<String, Value>,
+}
+
+/// Clones the object into the DTO and serializes the DTO.
+fn encode_cloned_dto<E: FlowEncoder>(object: &Object) -> Vec<u8> {
+    let dto = ObjectFlowDto {
+        flow_id: 1,
+        field1: object.field1.clone(),
+        field2: object.field2.clone(),
+        values: object.values.clone(),
+    };
+    E::serialize(&dto).unwrap()
+}
+
+/// Serializes the object by reference behind the header.
+fn encode_borrowed<E: FlowEncoder>(object: &Object) -> Vec<u8> {
+    serde_flow::flow::encode::<Object, E>(object, 1).unwrap()
+}
+
+fn bench_encoding(c: &mut Criterion) {
+    let object = create_object();
+
+    let mut group = c.benchmark_group("Encode");
+    group.bench_function("bincode cloned dto", |b| {
+        b.iter(|| black_box(encode_cloned_dto::<bincode::Encoder>(black_box(&object))));
+    });
+    group.bench_function("bincode borrowed", |b| {
+        b.iter(|| black_box(encode_borrowed::<bincode::Encoder>(black_box(&object))));
+    });
+    group.bench_function("json cloned dto", |b| {
+        b.iter(|| black_box(encode_cloned_dto::<json::Encoder>(black_box(&object))));
+    });
+    group.bench_function("json borrowed", |b| {
+        b.iter(|| black_box(encode_borrowed::<json::Encoder>(black_box(&object))));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_encoding);
+criterion_main!(benches);
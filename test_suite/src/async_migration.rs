@@ -108,9 +108,11 @@ async fn test_load_from_path_variant_not_found() -> Result<(), SerdeFlowError> {
         .await?;
 
     let result = CarNoMigration::load_from_path_async::<bincode::Encoder>(path.as_path()).await;
-    let Err(SerdeFlowError::VariantNotFound) = result else {
-        panic!("load_from_path no variant, must return VariantNotFound");
-    };
+    let error = result.err().expect("load_from_path no variant, must fail");
+    assert!(matches!(
+        error.without_path(),
+        SerdeFlowError::VariantUnknown { variant: 1, .. }
+    ));
     Ok(())
 }
 
@@ -147,9 +149,11 @@ async fn test_load_from_path_insufficient_variants() -> Result<(), SerdeFlowErro
         .await?;
 
     let result = CarWithMigration::load_from_path_async::<bincode::Encoder>(path.as_path()).await;
-    let Err(SerdeFlowError::VariantNotFound) = result else {
-        panic!("load_from_path no variant, must return VariantNotFound");
-    };
+    let error = result.err().expect("load_from_path no variant, must fail");
+    assert!(matches!(
+        error.without_path(),
+        SerdeFlowError::VariantUnknown { variant: 1, .. }
+    ));
     Ok(())
 }
 
@@ -226,7 +230,8 @@ async fn test_load_from_file_not_found() -> Result<(), SerdeFlowError> {
     let path = temp_dir.path().to_path_buf().join("not_found");
 
     let result = Car::load_from_path_async::<bincode::Encoder>(path.as_path()).await;
-    let Err(SerdeFlowError::FileNotFound) = result else {
+    let Err(SerdeFlowError::FileNotFound) = result.as_ref().map_err(SerdeFlowError::without_path)
+    else {
         panic!("load_from_path without file, must return FileNotFound");
     };
     Ok(())
@@ -239,7 +244,8 @@ async fn test_load_from_file_format_invalid() -> Result<(), SerdeFlowError> {
     let _ = std::fs::write(path.as_path(), Vec::new());
 
     let result = Car::load_from_path_async::<bincode::Encoder>(path.as_path()).await;
-    let Err(SerdeFlowError::FormatInvalid) = result else {
+    let Err(SerdeFlowError::FormatInvalid) = result.as_ref().map_err(SerdeFlowError::without_path)
+    else {
         panic!("load_from_path with empty file, must return FormatInvalid");
     };
     Ok(())
@@ -251,7 +257,8 @@ async fn test_migration_not_found() -> Result<(), SerdeFlowError> {
     let path = temp_dir.path().to_path_buf().join("not_found");
 
     let result = Car::migrate_async::<bincode::Encoder>(path.as_path()).await;
-    let Err(SerdeFlowError::FileNotFound) = result else {
+    let Err(SerdeFlowError::FileNotFound) = result.as_ref().map_err(SerdeFlowError::without_path)
+    else {
         panic!("Migrate without file, must return FileNotFound");
     };
     Ok(())
@@ -264,7 +271,8 @@ async fn test_migration_format_invalid() -> Result<(), SerdeFlowError> {
     let _ = std::fs::write(path.as_path(), Vec::new());
 
     let result = Car::migrate_async::<bincode::Encoder>(path.as_path()).await;
-    let Err(SerdeFlowError::FormatInvalid) = result else {
+    let Err(SerdeFlowError::FormatInvalid) = result.as_ref().map_err(SerdeFlowError::without_path)
+    else {
         panic!("Migrate with empty file, must return FormatInvalid");
     };
     Ok(())
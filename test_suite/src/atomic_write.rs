@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::encoder::bincode;
+use serde_flow::flow::{File, FileAsync, FileMigrate};
+use serde_flow::Flow;
+use std::path::Path;
+use tempfile::tempdir;
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 2, file(blocking, nonblocking))]
+#[variants(SettingsV1)]
+pub struct Settings {
+    pub theme: String,
+    pub font_size: u32,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file)]
+pub struct SettingsV1 {
+    pub theme: String,
+}
+
+impl From<SettingsV1> for Settings {
+    fn from(value: SettingsV1) -> Self {
+        Settings {
+            theme: value.theme,
+            font_size: 12,
+        }
+    }
+}
+
+fn dir_entries(dir: &Path) -> Vec<String> {
+    let mut entries: Vec<String> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    entries.sort();
+    entries
+}
+
+#[test]
+fn test_save_replaces_file_without_temp_files() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("settings");
+
+    let settings = Settings {
+        theme: "dark".to_string(),
+        font_size: 14,
+    };
+    settings
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    let settings = Settings {
+        theme: "light".to_string(),
+        font_size: 16,
+    };
+    settings
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    assert_eq!(dir_entries(temp_dir.path()), vec!["settings".to_string()]);
+    let settings = Settings::load_from_path::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(settings.theme.as_str(), "light");
+    assert_eq!(settings.font_size, 16);
+}
+
+#[tokio::test]
+async fn test_save_async_replaces_file_without_temp_files() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("settings");
+
+    let settings = Settings {
+        theme: "dark".to_string(),
+        font_size: 14,
+    };
+    settings
+        .save_to_path_async::<bincode::Encoder>(path.as_path())
+        .await
+        .unwrap();
+
+    assert_eq!(dir_entries(temp_dir.path()), vec!["settings".to_string()]);
+    let settings = Settings::load_from_path_async::<bincode::Encoder>(path.as_path())
+        .await
+        .unwrap();
+    assert_eq!(settings.theme.as_str(), "dark");
+}
+
+#[test]
+fn test_failed_save_keeps_existing_file() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("settings");
+
+    let settings = Settings {
+        theme: "dark".to_string(),
+        font_size: 14,
+    };
+    settings
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    // a directory can't be replaced by a file, the rename fails
+    let dir_path = temp_dir.path().to_path_buf().join("directory");
+    std::fs::create_dir(dir_path.as_path()).unwrap();
+    std::fs::write(dir_path.join("inner"), b"inner").unwrap();
+    let result = settings.save_to_path::<bincode::Encoder>(dir_path.as_path());
+    assert!(result.is_err());
+
+    assert_eq!(
+        dir_entries(temp_dir.path()),
+        vec!["directory".to_string(), "settings".to_string()]
+    );
+    let settings = Settings::load_from_path::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(settings.theme.as_str(), "dark");
+}
+
+#[test]
+fn test_migrate_replaces_file_without_temp_files() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("settings");
+
+    let settings = SettingsV1 {
+        theme: "dark".to_string(),
+    };
+    settings
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    Settings::migrate::<bincode::Encoder>(path.as_path()).unwrap();
+
+    assert_eq!(dir_entries(temp_dir.path()), vec!["settings".to_string()]);
+    let settings = Settings::load_from_path::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(settings.font_size, 12);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_save_keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("settings");
+
+    let settings = Settings {
+        theme: "dark".to_string(),
+        font_size: 14,
+    };
+    settings
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+    std::fs::set_permissions(path.as_path(), std::fs::Permissions::from_mode(0o600)).unwrap();
+
+    settings
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    let mode = std::fs::metadata(path.as_path())
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o600);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_save_through_symlink_replaces_target() {
+    let temp_dir = tempdir().unwrap();
+    let target = temp_dir.path().to_path_buf().join("settings");
+    let link = temp_dir.path().to_path_buf().join("link");
+
+    let settings = SettingsV1 {
+        theme: "dark".to_string(),
+    };
+    settings
+        .save_to_path::<bincode::Encoder>(target.as_path())
+        .unwrap();
+    std::os::unix::fs::symlink(target.as_path(), link.as_path()).unwrap();
+
+    Settings::migrate::<bincode::Encoder>(link.as_path()).unwrap();
+
+    assert!(std::fs::symlink_metadata(link.as_path())
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    let settings = Settings::load_from_path::<bincode::Encoder>(target.as_path()).unwrap();
+    assert_eq!(settings.font_size, 12);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_save_through_dangling_symlink_creates_target() {
+    let temp_dir = tempdir().unwrap();
+    let target = temp_dir.path().to_path_buf().join("settings");
+    let link = temp_dir.path().to_path_buf().join("link");
+    // the relative symlink points to the file next to it, which doesn't exist yet
+    std::os::unix::fs::symlink("settings", link.as_path()).unwrap();
+
+    let settings = Settings {
+        theme: "dark".to_string(),
+        font_size: 10,
+    };
+    settings
+        .save_to_path::<bincode::Encoder>(link.as_path())
+        .unwrap();
+
+    assert!(std::fs::symlink_metadata(link.as_path())
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    let settings = Settings::load_from_path::<bincode::Encoder>(target.as_path()).unwrap();
+    assert_eq!(settings.font_size, 10);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_save_async_through_symlink_replaces_target() {
+    let temp_dir = tempdir().unwrap();
+    let target = temp_dir.path().to_path_buf().join("settings");
+    let link = temp_dir.path().to_path_buf().join("link");
+
+    let settings = Settings {
+        theme: "dark".to_string(),
+        font_size: 14,
+    };
+    settings
+        .save_to_path_async::<bincode::Encoder>(target.as_path())
+        .await
+        .unwrap();
+    std::os::unix::fs::symlink(target.as_path(), link.as_path()).unwrap();
+
+    let settings = Settings {
+        theme: "light".to_string(),
+        font_size: 16,
+    };
+    settings
+        .save_to_path_async::<bincode::Encoder>(link.as_path())
+        .await
+        .unwrap();
+
+    assert!(std::fs::symlink_metadata(link.as_path())
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    let settings = Settings::load_from_path::<bincode::Encoder>(target.as_path()).unwrap();
+    assert_eq!(settings.theme.as_str(), "light");
+}
@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::encoder::bincode;
+use serde_flow::error::SerdeFlowError;
+use serde_flow::flow::{File, FileAsync, FileMigrate, FileMigrateAsync};
+use serde_flow::fs::backup_path;
+use serde_flow::Flow;
+use tempfile::tempdir;
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 2, file(blocking, nonblocking, backup))]
+#[variants(ProfileV1)]
+pub struct Profile {
+    pub name: String,
+    pub age: u8,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file(blocking, nonblocking))]
+pub struct ProfileV1 {
+    pub name: String,
+}
+
+impl From<ProfileV1> for Profile {
+    fn from(value: ProfileV1) -> Self {
+        Profile {
+            name: value.name,
+            age: 0,
+        }
+    }
+}
+
+#[test]
+fn test_migrate_keeps_backup() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("profile");
+
+    let profile = ProfileV1 {
+        name: "John".to_string(),
+    };
+    profile
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+    let original = std::fs::read(path.as_path()).unwrap();
+
+    Profile::migrate::<bincode::Encoder>(path.as_path()).unwrap();
+
+    let backup = backup_path(path.as_path(), 1);
+    assert_eq!(std::fs::read(backup.as_path()).unwrap(), original);
+    let profile = ProfileV1::load_from_path::<bincode::Encoder>(backup.as_path()).unwrap();
+    assert_eq!(profile.name.as_str(), "John");
+}
+
+#[test]
+fn test_migrate_current_variant_without_backup() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("profile");
+
+    let profile = Profile {
+        name: "John".to_string(),
+        age: 30,
+    };
+    profile
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    Profile::migrate::<bincode::Encoder>(path.as_path()).unwrap();
+
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_rollback_restores_backup() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("profile");
+
+    let profile = ProfileV1 {
+        name: "John".to_string(),
+    };
+    profile
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    let migrated = Profile::load_and_migrate::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(migrated.age, 0);
+    assert!(ProfileV1::load_from_path::<bincode::Encoder>(path.as_path()).is_err());
+
+    let variant = Profile::rollback(path.as_path()).unwrap();
+    assert_eq!(variant, 1);
+
+    let profile = ProfileV1::load_from_path::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(profile.name.as_str(), "John");
+    assert!(!backup_path(path.as_path(), 1).exists());
+}
+
+#[test]
+fn test_rollback_without_backup_returns_error() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("profile");
+
+    let profile = Profile {
+        name: "John".to_string(),
+        age: 30,
+    };
+    profile
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    let result = Profile::rollback(path.as_path());
+    assert!(matches!(result, Err(SerdeFlowError::BackupNotFound)));
+}
+
+#[tokio::test]
+async fn test_migrate_async_and_rollback() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("profile");
+
+    let profile = ProfileV1 {
+        name: "John".to_string(),
+    };
+    profile
+        .save_to_path_async::<bincode::Encoder>(path.as_path())
+        .await
+        .unwrap();
+
+    Profile::migrate_async::<bincode::Encoder>(path.as_path())
+        .await
+        .unwrap();
+    assert!(backup_path(path.as_path(), 1).exists());
+
+    let variant = Profile::rollback_async(path.as_path()).await.unwrap();
+    assert_eq!(variant, 1);
+    let profile = ProfileV1::load_from_path_async::<bincode::Encoder>(path.as_path())
+        .await
+        .unwrap();
+    assert_eq!(profile.name.as_str(), "John");
+}
+
+mod zerocopy {
+    use rkyv::{Archive, Deserialize, Serialize};
+    use serde_flow::flow::zerocopy::{File, FileMigrate};
+    use serde_flow::fs::backup_path;
+    use serde_flow::Flow;
+    use tempfile::tempdir;
+
+    #[derive(Archive, Serialize, Deserialize, Flow)]
+    #[archive(check_bytes)]
+    #[flow(variant = 2, file(backup), zerocopy)]
+    #[variants(ProfileV1)]
+    pub struct Profile {
+        pub name: String,
+        pub age: u8,
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Flow)]
+    #[archive(check_bytes)]
+    #[flow(variant = 1, file, zerocopy)]
+    pub struct ProfileV1 {
+        pub name: String,
+    }
+
+    impl From<ProfileV1> for Profile {
+        fn from(value: ProfileV1) -> Self {
+            Profile {
+                name: value.name,
+                age: 0,
+            }
+        }
+    }
+
+    #[test]
+    fn test_migrate_and_rollback() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().to_path_buf().join("profile");
+
+        let profile = ProfileV1 {
+            name: "John".to_string(),
+        };
+        profile.save_to_path(path.as_path()).unwrap();
+
+        Profile::migrate(path.as_path()).unwrap();
+        assert!(backup_path(path.as_path(), 1).exists());
+        assert!(ProfileV1::load_from_path(path.as_path()).is_err());
+
+        let variant = Profile::rollback(path.as_path()).unwrap();
+        assert_eq!(variant, 1);
+        let reader = ProfileV1::load_from_path(path.as_path()).unwrap();
+        assert_eq!(reader.archive().name, "John");
+    }
+}
@@ -0,0 +1,164 @@
+use rkyv::{AlignedVec, Archive, Deserialize, Serialize};
+use serde_flow::encoder::zerocopy::Encoder;
+use serde_flow::error::SerdeFlowError;
+use serde_flow::flow::header::{Header, HEADER_SIZE};
+use serde_flow::flow::zerocopy::Bytes;
+use serde_flow::Flow;
+
+#[derive(Debug, PartialEq, Archive, Serialize, Deserialize, Flow)]
+#[archive(check_bytes)]
+#[flow(variant = 2, bytes, zerocopy)]
+#[variants(PacketV1)]
+pub struct Packet {
+    pub id: u64,
+    pub samples: Vec<u64>,
+}
+
+#[derive(Archive, Serialize, Deserialize, Flow)]
+#[archive(check_bytes)]
+#[flow(variant = 1, bytes, zerocopy)]
+pub struct PacketV1 {
+    pub id: u64,
+}
+
+impl From<PacketV1> for Packet {
+    fn from(value: PacketV1) -> Self {
+        Packet {
+            id: value.id,
+            samples: Vec::new(),
+        }
+    }
+}
+
+/// A release of the packet newer than the one above.
+#[derive(Archive, Serialize, Deserialize, Flow)]
+#[archive(check_bytes)]
+#[flow(variant = 3, bytes, zerocopy)]
+pub struct PacketV3 {
+    pub id: u64,
+}
+
+fn packet(id: u64) -> Packet {
+    Packet {
+        id,
+        samples: (0..64).map(|sample| sample * id).collect(),
+    }
+}
+
+#[test]
+fn test_encode_into_decode_ref() {
+    let mut buffer = AlignedVec::new();
+    packet(3).encode_into(&mut buffer).unwrap();
+
+    let view = Packet::decode_ref(&buffer).unwrap();
+    assert_eq!(view.id, 3);
+    assert_eq!(view.samples[10], 30);
+    assert_eq!(view.deserialize().unwrap(), packet(3));
+}
+
+#[test]
+fn test_encode_into_matches_encode() {
+    let mut buffer = AlignedVec::new();
+    packet(5).encode_into(&mut buffer).unwrap();
+    assert_eq!(buffer.as_slice(), packet(5).encode().unwrap().as_slice());
+}
+
+#[test]
+fn test_encode_into_reuses_buffer() {
+    let mut buffer = AlignedVec::with_capacity(4096);
+    let allocation = buffer.as_ptr();
+
+    for id in 0..8 {
+        packet(id).encode_into(&mut buffer).unwrap();
+        assert_eq!(buffer.as_ptr(), allocation);
+        assert_eq!(Packet::decode_ref(&buffer).unwrap().id, id);
+    }
+}
+
+#[test]
+fn test_decode_ref_older_variant_returns_error() {
+    let mut buffer = AlignedVec::new();
+    PacketV1 { id: 1 }.encode_into(&mut buffer).unwrap();
+
+    let result = Packet::decode_ref(&buffer);
+    assert!(matches!(
+        result,
+        Err(SerdeFlowError::VariantMismatch {
+            expected: 2,
+            found: 1,
+            ..
+        })
+    ));
+
+    // the older variants are migrated by decode
+    let reader = Packet::decode(buffer.to_vec()).unwrap();
+    assert_eq!(reader.id, 1);
+}
+
+#[test]
+fn test_decode_ref_newer_variant_returns_error() {
+    let mut buffer = AlignedVec::new();
+    PacketV3 { id: 1 }.encode_into(&mut buffer).unwrap();
+
+    let result = Packet::decode_ref(&buffer);
+    assert!(matches!(
+        result,
+        Err(SerdeFlowError::VariantUnsupported {
+            variant: 3,
+            latest: 2,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_decode_ref_corrupted_returns_error() {
+    let mut buffer = AlignedVec::new();
+    packet(7).encode_into(&mut buffer).unwrap();
+    let last = buffer.len() - 1;
+    buffer[last] ^= 0xFF;
+
+    let result = Packet::decode_ref(&buffer);
+    assert!(matches!(
+        result,
+        Err(SerdeFlowError::ChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_decode_ref_other_encoder_returns_error() {
+    let bytes = Header::new(2, 2).with_payload(b"payload");
+    let result = Packet::decode_ref(&bytes);
+    assert!(matches!(
+        result,
+        Err(SerdeFlowError::EncoderMismatch {
+            expected: Encoder::ID,
+            found: 2
+        })
+    ));
+}
+
+#[test]
+fn test_decode_ref_misaligned_returns_error() {
+    let mut buffer = AlignedVec::new();
+    packet(9).encode_into(&mut buffer).unwrap();
+
+    let mut shifted = AlignedVec::new();
+    shifted.push(0);
+    shifted.extend_from_slice(&buffer);
+
+    let result = Packet::decode_ref(&shifted[1..]);
+    assert!(matches!(result, Err(SerdeFlowError::Decode { .. })));
+    assert_eq!(Packet::decode(shifted[1..].to_vec()).unwrap().id, 9);
+}
+
+#[test]
+fn test_archived_view_is_copy() {
+    let mut buffer = AlignedVec::new();
+    packet(4).encode_into(&mut buffer).unwrap();
+    assert!(buffer.len() > HEADER_SIZE);
+
+    let view = Packet::decode_ref(&buffer).unwrap();
+    let copy = view;
+    assert_eq!(view.samples.len(), copy.archive().samples.len());
+}
@@ -94,5 +94,12 @@ pub fn decode_from_not_found_variant_returns_error() {
     let mystruct20 = MyStructV20 { value: 20 };
     let bytes = mystruct20.encode::<bincode::Encoder>().unwrap();
     let result = MyStruct::decode::<bincode::Encoder>(&bytes);
-    assert!(matches!(result, Err(SerdeFlowError::VariantNotFound)))
+    assert!(matches!(
+        result,
+        Err(SerdeFlowError::VariantUnsupported {
+            variant: 20,
+            latest: 3,
+            ..
+        })
+    ))
 }
@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::encoder::{bincode, json};
+use serde_flow::flow::{Bytes, File, FileMigrate};
+use serde_flow::Flow;
+use tempfile::tempdir;
+
+// Every variant declares only how to come from its immediate predecessor:
+// OrderV1 -> OrderV2 -> OrderV3 -> Order
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 4, file, bytes)]
+#[variants(OrderV3)]
+pub struct Order {
+    pub id: u64,
+    pub items: Vec<String>,
+    pub total_cents: u64,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 3, file, bytes)]
+#[variants(OrderV2)]
+pub struct OrderV3 {
+    pub id: u64,
+    pub items: Vec<String>,
+    pub total: f64,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 2, file, bytes)]
+#[variants(OrderV1)]
+pub struct OrderV2 {
+    pub id: u64,
+    pub item: String,
+    pub total: f64,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file, bytes)]
+pub struct OrderV1 {
+    pub item: String,
+    pub total: f64,
+}
+
+impl From<OrderV1> for OrderV2 {
+    fn from(value: OrderV1) -> Self {
+        OrderV2 {
+            id: 0,
+            item: value.item,
+            total: value.total,
+        }
+    }
+}
+
+impl From<OrderV2> for OrderV3 {
+    fn from(value: OrderV2) -> Self {
+        OrderV3 {
+            id: value.id,
+            items: vec![value.item],
+            total: value.total,
+        }
+    }
+}
+
+impl From<OrderV3> for Order {
+    fn from(value: OrderV3) -> Self {
+        Order {
+            id: value.id,
+            items: value.items,
+            total_cents: (value.total * 100.0).round() as u64,
+        }
+    }
+}
+
+fn order_v1() -> OrderV1 {
+    OrderV1 {
+        item: "Book".to_string(),
+        total: 12.5,
+    }
+}
+
+#[test]
+fn test_load_from_path_through_chain() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("order");
+
+    order_v1()
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    let order = Order::load_from_path::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(order.id, 0);
+    assert_eq!(order.items, vec!["Book".to_string()]);
+    assert_eq!(order.total_cents, 1250);
+}
+
+#[test]
+fn test_migrate_through_chain() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("order");
+
+    let order_v2 = OrderV2 {
+        id: 7,
+        item: "Pen".to_string(),
+        total: 1.25,
+    };
+    order_v2
+        .save_to_path::<json::Encoder>(path.as_path())
+        .unwrap();
+
+    Order::migrate::<json::Encoder>(path.as_path()).unwrap();
+
+    let order = Order::load_from_path::<json::Encoder>(path.as_path()).unwrap();
+    assert_eq!(order.id, 7);
+    assert_eq!(order.total_cents, 125);
+}
+
+#[test]
+fn test_decode_through_chain() {
+    let bytes = order_v1().encode::<bincode::Encoder>().unwrap();
+
+    let order_v3 = OrderV3::decode::<bincode::Encoder>(&bytes).unwrap();
+    assert_eq!(order_v3.items, vec!["Book".to_string()]);
+
+    let order = Order::decode::<bincode::Encoder>(&bytes).unwrap();
+    assert_eq!(order.total_cents, 1250);
+}
+
+#[test]
+fn test_direct_variant_has_priority_over_chain() {
+    use serde_flow::flow::Migrate;
+
+    #[derive(Serialize, Deserialize, Flow)]
+    #[flow(variant = 5, bytes)]
+    #[variants(OrderV3, OrderV1)]
+    pub struct Summary {
+        pub description: String,
+    }
+
+    impl From<OrderV3> for Summary {
+        fn from(value: OrderV3) -> Self {
+            Summary {
+                description: format!("chain: {}", value.items.join(", ")),
+            }
+        }
+    }
+
+    impl From<OrderV1> for Summary {
+        fn from(value: OrderV1) -> Self {
+            Summary {
+                description: format!("direct: {}", value.item),
+            }
+        }
+    }
+
+    assert!(Summary::accepts_variant(2));
+    assert!(!Summary::accepts_variant(4));
+
+    let bytes = order_v1().encode::<bincode::Encoder>().unwrap();
+    let summary = Summary::decode::<bincode::Encoder>(&bytes).unwrap();
+    assert_eq!(summary.description.as_str(), "direct: Book");
+}
+
+mod zerocopy {
+    use rkyv::{Archive, Deserialize, Serialize};
+    use serde_flow::flow::zerocopy::{Bytes, File};
+    use serde_flow::Flow;
+    use tempfile::tempdir;
+
+    #[derive(Archive, Serialize, Deserialize, Flow)]
+    #[archive(check_bytes)]
+    #[flow(variant = 3, file, bytes, zerocopy)]
+    #[variants(PointV2)]
+    pub struct Point {
+        pub x: i64,
+        pub y: i64,
+        pub z: i64,
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Flow)]
+    #[archive(check_bytes)]
+    #[flow(variant = 2, zerocopy)]
+    #[variants(PointV1)]
+    pub struct PointV2 {
+        pub x: i64,
+        pub y: i64,
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Flow)]
+    #[archive(check_bytes)]
+    #[flow(variant = 1, file, bytes, zerocopy)]
+    pub struct PointV1 {
+        pub x: i32,
+    }
+
+    impl From<PointV1> for PointV2 {
+        fn from(value: PointV1) -> Self {
+            PointV2 {
+                x: i64::from(value.x),
+                y: 0,
+            }
+        }
+    }
+
+    impl From<PointV2> for Point {
+        fn from(value: PointV2) -> Self {
+            Point {
+                x: value.x,
+                y: value.y,
+                z: 0,
+            }
+        }
+    }
+
+    #[test]
+    fn test_load_from_path_through_chain() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().to_path_buf().join("point");
+
+        PointV1 { x: 42 }.save_to_path(path.as_path()).unwrap();
+
+        let reader = Point::load_from_path(path.as_path()).unwrap();
+        let point = reader.archive();
+        assert_eq!(point.x, 42);
+        assert_eq!(point.z, 0);
+    }
+
+    #[test]
+    fn test_decode_through_chain() {
+        let bytes = PointV1 { x: 42 }.encode().unwrap();
+
+        assert!(Point::decode(bytes).is_ok());
+    }
+}
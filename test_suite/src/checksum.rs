@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::encoder::bincode;
+use serde_flow::error::SerdeFlowError;
+use serde_flow::flow::header::HEADER_SIZE;
+use serde_flow::flow::{Bytes, File, FileAsync};
+use serde_flow::Flow;
+use std::path::Path;
+use tempfile::tempdir;
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file(blocking, nonblocking), bytes)]
+pub struct Account {
+    pub owner: String,
+    pub balance: u64,
+}
+
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Flow)]
+#[archive(check_bytes)]
+#[flow(variant = 1, file, zerocopy)]
+pub struct ArchivedAccount {
+    pub owner: String,
+    pub balance: u64,
+}
+
+fn account() -> Account {
+    Account {
+        owner: "John Doe".to_string(),
+        balance: 1000,
+    }
+}
+
+fn flip_last_byte(path: &Path) {
+    let mut bytes = std::fs::read(path).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xFF;
+    std::fs::write(path, bytes).unwrap();
+}
+
+#[test]
+fn test_load_corrupted_file_returns_error() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("account");
+
+    account()
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+    flip_last_byte(path.as_path());
+
+    let result = Account::load_from_path::<bincode::Encoder>(path.as_path());
+    assert!(matches!(
+        result.as_ref().map_err(SerdeFlowError::without_path),
+        Err(SerdeFlowError::ChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_load_truncated_file_returns_error() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("account");
+
+    account()
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+    let bytes = std::fs::read(path.as_path()).unwrap();
+    std::fs::write(path.as_path(), &bytes[..HEADER_SIZE + 4]).unwrap();
+
+    let result = Account::load_from_path::<bincode::Encoder>(path.as_path());
+    assert!(matches!(
+        result.as_ref().map_err(SerdeFlowError::without_path),
+        Err(SerdeFlowError::ChecksumMismatch { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_load_async_corrupted_file_returns_error() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("account");
+
+    account()
+        .save_to_path_async::<bincode::Encoder>(path.as_path())
+        .await
+        .unwrap();
+    flip_last_byte(path.as_path());
+
+    let result = Account::load_from_path_async::<bincode::Encoder>(path.as_path()).await;
+    assert!(matches!(
+        result.as_ref().map_err(SerdeFlowError::without_path),
+        Err(SerdeFlowError::ChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_decode_corrupted_bytes_returns_error() {
+    let mut bytes = account().encode::<bincode::Encoder>().unwrap();
+    bytes[HEADER_SIZE] ^= 0xFF;
+
+    let result = Account::decode::<bincode::Encoder>(&bytes);
+    let Err(SerdeFlowError::ChecksumMismatch { expected, found }) = result else {
+        panic!("decode of corrupted bytes, must return ChecksumMismatch");
+    };
+    assert_ne!(expected, found);
+}
+
+#[test]
+fn test_zerocopy_load_corrupted_file_returns_error() {
+    use serde_flow::flow::zerocopy::File;
+
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("account");
+
+    let account = ArchivedAccount {
+        owner: "John Doe".to_string(),
+        balance: 1000,
+    };
+    account.save_to_path(path.as_path()).unwrap();
+    flip_last_byte(path.as_path());
+
+    let result = ArchivedAccount::load_from_path(path.as_path());
+    assert!(matches!(
+        result.as_ref().map_err(SerdeFlowError::without_path),
+        Err(SerdeFlowError::ChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_decode_corrupted_header_returns_error() {
+    let mut bytes = account().encode::<bincode::Encoder>().unwrap();
+    bytes[8] ^= 0x02;
+
+    let result = Account::decode::<bincode::Encoder>(&bytes);
+    assert!(matches!(
+        result,
+        Err(SerdeFlowError::ChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_decode_without_checksum_flag_returns_error() {
+    let mut bytes = account().encode::<bincode::Encoder>().unwrap();
+    bytes[6] &= !0x01;
+
+    let result = Account::decode::<bincode::Encoder>(&bytes);
+    assert!(matches!(
+        result,
+        Err(SerdeFlowError::ChecksumMismatch { .. })
+    ));
+}
@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::encoder::{bincode, json, FlowEncoder};
+use serde_flow::error::SerdeFlowError;
+use serde_flow::flow::header::{Header, HEADER_SIZE};
+use serde_flow::flow::{Bytes, File, FileAsync};
+use serde_flow::Flow;
+use tempfile::tempdir;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Flow)]
+#[flow(variant = 2, file(blocking, nonblocking), bytes)]
+#[variants(MeasureV1)]
+pub struct Measure {
+    pub name: String,
+    pub values: Vec<u64>,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file(blocking, nonblocking), bytes)]
+pub struct MeasureV1 {
+    pub name: String,
+}
+
+impl From<MeasureV1> for Measure {
+    fn from(value: MeasureV1) -> Self {
+        Measure {
+            name: value.name,
+            values: Vec::new(),
+        }
+    }
+}
+
+fn measure() -> Measure {
+    Measure {
+        name: "load".to_string(),
+        values: vec![1, 2, 300],
+    }
+}
+
+#[test]
+fn test_bincode_options_round_trip() {
+    let encoder = bincode::Encoder::with_options(true, true, None);
+    let bytes = measure().encode_with(&encoder).unwrap();
+
+    let (header, _) = Header::split(&bytes).unwrap();
+    assert_eq!(header.encoder, bincode::Encoder::ID);
+    assert_eq!(Measure::decode_with(&encoder, &bytes).unwrap(), measure());
+
+    // varint payloads are smaller than the fixed-size ones of the default encoder
+    let default_bytes = measure().encode::<bincode::Encoder>().unwrap();
+    assert!(bytes.len() < default_bytes.len());
+}
+
+#[test]
+fn test_bincode_default_options_match_encoder() {
+    let encoder = bincode::Encoder::with_options(false, false, None);
+    let bytes = measure().encode_with(&encoder).unwrap();
+
+    assert_eq!(bytes, measure().encode::<bincode::Encoder>().unwrap());
+    assert_eq!(
+        Measure::decode::<bincode::Encoder>(&bytes).unwrap(),
+        measure()
+    );
+}
+
+#[test]
+fn test_bincode_limit_returns_error() {
+    let encoder = bincode::Encoder::with_options(false, false, Some(8));
+    assert!(measure().encode_with(&encoder).is_err());
+
+    let encoder = bincode::Encoder::with_options(false, false, Some(1024));
+    assert!(measure().encode_with(&encoder).is_ok());
+}
+
+#[test]
+fn test_bincode_limit_applies_to_decode() {
+    let bytes = measure().encode::<bincode::Encoder>().unwrap();
+
+    let encoder = bincode::Encoder::with_options(false, false, Some(8));
+    let result = Measure::decode_with(&encoder, &bytes);
+    assert!(matches!(result, Err(SerdeFlowError::Decode { .. })));
+}
+
+#[test]
+fn test_bincode_options_recorded_in_header() {
+    let encoder = bincode::Encoder::with_options(true, true, None);
+    let bytes = measure().encode_with(&encoder).unwrap();
+
+    let (header, _) = Header::split(&bytes).unwrap();
+    assert_eq!(
+        header.encoder_flags(),
+        bincode::FLAG_VARINT | bincode::FLAG_BIG_ENDIAN
+    );
+    assert_eq!(
+        bincode::Config::from_flags(header.encoder_flags()),
+        bincode::Encoder::with_options(true, true, None)
+    );
+}
+
+#[test]
+fn test_decode_with_other_bincode_options_returns_error() {
+    let encoder = bincode::Encoder::with_options(true, false, None);
+    let bytes = measure().encode_with(&encoder).unwrap();
+
+    let result = Measure::decode::<bincode::Encoder>(&bytes);
+    assert!(matches!(
+        result,
+        Err(SerdeFlowError::EncoderOptionsMismatch {
+            encoder: bincode::Encoder::ID,
+            expected: 0,
+            found: bincode::FLAG_VARINT,
+        })
+    ));
+
+    let encoder = bincode::Encoder::with_options(true, true, None);
+    let result = Measure::decode_with(&encoder, &bytes);
+    assert!(matches!(
+        result,
+        Err(SerdeFlowError::EncoderOptionsMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_auto_load_uses_recorded_bincode_options() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("measure");
+
+    let encoder = bincode::Encoder::with_options(true, true, None);
+    measure()
+        .save_to_path_with(&encoder, path.as_path())
+        .unwrap();
+
+    let loaded = Measure::load_from_path_auto(path.as_path()).unwrap();
+    assert_eq!(loaded, measure());
+
+    let bytes = std::fs::read(path.as_path()).unwrap();
+    assert_eq!(Measure::decode_auto(&bytes).unwrap(), measure());
+}
+
+#[test]
+fn test_json_pretty_save_and_load() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("measure.json");
+
+    measure()
+        .save_to_path_with(&json::Encoder::pretty(), path.as_path())
+        .unwrap();
+
+    let bytes = std::fs::read(path.as_path()).unwrap();
+    let payload = std::str::from_utf8(&bytes[HEADER_SIZE..]).unwrap();
+    assert!(payload.contains("\n  \"name\": \"load\""));
+
+    // pretty json is read by the default encoder as well
+    let loaded = Measure::load_from_path::<json::Encoder>(path.as_path()).unwrap();
+    assert_eq!(loaded, measure());
+    let loaded = Measure::load_from_path_with(&json::Encoder::pretty(), path.as_path()).unwrap();
+    assert_eq!(loaded, measure());
+}
+
+#[test]
+fn test_load_with_options_migrates() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("measure");
+    let encoder = bincode::Encoder::with_options(true, false, Some(1024));
+
+    let measure = MeasureV1 {
+        name: "old".to_string(),
+    };
+    measure.save_to_path_with(&encoder, path.as_path()).unwrap();
+
+    let measure = Measure::load_from_path_with(&encoder, path.as_path()).unwrap();
+    assert_eq!(measure.name.as_str(), "old");
+    assert!(measure.values.is_empty());
+}
+
+#[tokio::test]
+async fn test_save_and_load_async_with_options() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("measure");
+    let encoder = bincode::Encoder::with_options(true, true, None);
+
+    measure()
+        .save_to_path_async_with(&encoder, path.as_path())
+        .await
+        .unwrap();
+
+    let loaded = Measure::load_from_path_async_with(&encoder, path.as_path())
+        .await
+        .unwrap();
+    assert_eq!(loaded, measure());
+}
@@ -0,0 +1,202 @@
+use rkyv::{Archive, Deserialize, Serialize};
+use serde_flow::encoder::{bincode, json, zerocopy, FlowEncoder};
+use serde_flow::error::SerdeFlowError;
+use serde_flow::flow::header::Header;
+use serde_flow::flow::zerocopy::{File, FileMigrate};
+use serde_flow::{Flow, FlowInfo};
+use tempfile::tempdir;
+
+/// The type stored with bincode or json before moving to rkyv.
+#[derive(serde::Serialize, serde::Deserialize, Flow)]
+#[flow(variant = 1, file, bytes)]
+pub struct CarV1 {
+    pub model: String,
+}
+
+#[derive(Archive, Serialize, Deserialize, Flow)]
+#[archive(check_bytes)]
+#[flow(variant = 2, file, bytes, zerocopy)]
+#[variants(serde(CarV1))]
+pub struct CarV2 {
+    pub model: String,
+    pub doors: u8,
+}
+
+impl From<CarV1> for CarV2 {
+    fn from(value: CarV1) -> Self {
+        CarV2 {
+            model: value.model,
+            doors: 4,
+        }
+    }
+}
+
+#[derive(Archive, Serialize, Deserialize, Flow)]
+#[archive(check_bytes)]
+#[flow(variant = 3, file, zerocopy)]
+#[variants(CarV2)]
+pub struct Car {
+    pub model: String,
+    pub doors: u8,
+    pub electric: bool,
+}
+
+impl From<CarV2> for Car {
+    fn from(value: CarV2) -> Self {
+        Car {
+            model: value.model,
+            doors: value.doors,
+            electric: false,
+        }
+    }
+}
+
+/// Exports the zerocopy type back to a serde encoder.
+#[derive(serde::Serialize, serde::Deserialize, Flow)]
+#[flow(variant = 4, file, bytes)]
+#[variants(zerocopy(Car))]
+pub struct CarExport {
+    pub model: String,
+    pub electric: bool,
+}
+
+impl From<Car> for CarExport {
+    fn from(value: Car) -> Self {
+        CarExport {
+            model: value.model,
+            electric: value.electric,
+        }
+    }
+}
+
+fn read_header(path: &std::path::Path) -> Header {
+    Header::parse(&std::fs::read(path).unwrap()).unwrap()
+}
+
+#[test]
+fn test_migrate_serde_file_into_zerocopy() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("car");
+
+    let car = CarV1 {
+        model: "Saab".to_string(),
+    };
+    serde_flow::flow::File::save_to_path::<bincode::Encoder>(&car, path.as_path()).unwrap();
+
+    CarV2::migrate(path.as_path()).unwrap();
+
+    let header = read_header(path.as_path());
+    assert_eq!(header.encoder, zerocopy::Encoder::ID);
+    assert_eq!(header.variant, 2);
+
+    let reader = CarV2::load_from_path(path.as_path()).unwrap();
+    let car = reader.archive();
+    assert_eq!(car.model, "Saab");
+    assert_eq!(car.doors, 4);
+}
+
+#[test]
+fn test_migrate_serde_file_through_zerocopy_chain() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("car");
+
+    let car = CarV1 {
+        model: "Volvo".to_string(),
+    };
+    serde_flow::flow::File::save_to_path::<json::Encoder>(&car, path.as_path()).unwrap();
+
+    let reader = Car::load_and_migrate(path.as_path()).unwrap();
+    let car = reader.archive();
+    assert_eq!(car.model, "Volvo");
+    assert_eq!(car.doors, 4);
+    assert!(!car.electric);
+
+    let header = read_header(path.as_path());
+    assert_eq!(header.encoder, zerocopy::Encoder::ID);
+    assert_eq!(header.variant, 3);
+}
+
+#[test]
+fn test_decode_serde_bytes_into_zerocopy() {
+    use serde_flow::flow::zerocopy::Bytes;
+
+    let car = CarV1 {
+        model: "Lada".to_string(),
+    };
+    let bytes = serde_flow::flow::Bytes::encode::<bincode::Encoder>(&car).unwrap();
+
+    assert!(CarV2::decode(bytes).is_ok());
+}
+
+#[test]
+fn test_export_zerocopy_file_to_serde() {
+    use serde_flow::flow::FileMigrate;
+
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("car");
+
+    let car = Car {
+        model: "Tesla".to_string(),
+        doors: 4,
+        electric: true,
+    };
+    car.save_to_path(path.as_path()).unwrap();
+
+    let car = CarExport::load_and_migrate::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(car.model.as_str(), "Tesla");
+    assert!(car.electric);
+
+    let header = read_header(path.as_path());
+    assert_eq!(header.encoder, bincode::Encoder::ID);
+    assert_eq!(header.variant, 4);
+}
+
+#[test]
+fn test_auto_load_zerocopy_file_into_serde_type() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("car");
+
+    let car = Car {
+        model: "Tesla".to_string(),
+        doors: 4,
+        electric: true,
+    };
+    car.save_to_path(path.as_path()).unwrap();
+
+    let car = <CarExport as serde_flow::flow::File<CarExport>>::load_from_path_auto(path.as_path())
+        .unwrap();
+    assert_eq!(car.model.as_str(), "Tesla");
+    assert!(car.electric);
+
+    let bytes = std::fs::read(path.as_path()).unwrap();
+    let car = serde_flow::flow::decode_auto::<CarExport>(&bytes).unwrap();
+    assert_eq!(car.model.as_str(), "Tesla");
+}
+
+#[test]
+fn test_accepted_variants_include_other_family() {
+    assert_eq!(CarV2::ACCEPTED_VARIANTS, &[2, 1]);
+    assert_eq!(Car::ACCEPTED_VARIANTS, &[3, 2, 1]);
+    assert_eq!(CarExport::ACCEPTED_VARIANTS, &[4, 3, 2, 1]);
+}
+
+#[test]
+fn test_load_serde_file_without_serde_variants_returns_error() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("car");
+
+    let car = CarExport {
+        model: "Fiat".to_string(),
+        electric: false,
+    };
+    serde_flow::flow::File::save_to_path::<bincode::Encoder>(&car, path.as_path()).unwrap();
+
+    let result = Car::load_from_path(path.as_path());
+    assert!(matches!(
+        result.as_ref().map_err(SerdeFlowError::without_path),
+        Err(SerdeFlowError::EncoderMismatch {
+            expected: 1,
+            found: 2
+        })
+    ));
+}
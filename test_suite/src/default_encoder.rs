@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::encoder::{bincode, json};
+use serde_flow::flow::{Bytes, File};
+use serde_flow::Flow;
+use tempfile::tempdir;
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 2, file(blocking, nonblocking), bytes, encoder = bincode)]
+#[variants(UserV1)]
+pub struct User {
+    pub name: String,
+    pub age: u8,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file(blocking, nonblocking), bytes, encoder = bincode)]
+pub struct UserV1 {
+    pub name: String,
+}
+
+impl From<UserV1> for User {
+    fn from(value: UserV1) -> Self {
+        User {
+            name: value.name,
+            age: 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, bytes, encoder = serde_flow::encoder::json::Encoder)]
+pub struct Note<T> {
+    pub text: T,
+}
+
+#[test]
+fn test_save_and_load() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("user");
+
+    UserV1 {
+        name: "Jan".to_string(),
+    }
+    .save(path.as_path())
+    .unwrap();
+
+    let user = User::load(path.as_path()).unwrap();
+    assert_eq!(user.name.as_str(), "Jan");
+    assert_eq!(user.age, 0);
+
+    // the default encoder is the same one the generic methods use
+    let user = User::load_from_path::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(user.name.as_str(), "Jan");
+    assert!(User::load_from_path::<json::Encoder>(path.as_path()).is_err());
+}
+
+#[tokio::test]
+async fn test_save_and_load_async() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("user");
+
+    let user = User {
+        name: "Eva".to_string(),
+        age: 31,
+    };
+    user.save_async(path.as_path()).await.unwrap();
+
+    let user = User::load_async(path.as_path()).await.unwrap();
+    assert_eq!(user.name.as_str(), "Eva");
+    assert_eq!(user.age, 31);
+}
+
+#[test]
+fn test_to_and_from_bytes() {
+    let bytes = UserV1 {
+        name: "Ola".to_string(),
+    }
+    .to_bytes()
+    .unwrap();
+    let user = User::from_bytes(&bytes).unwrap();
+    assert_eq!(user.name.as_str(), "Ola");
+
+    let note = Note {
+        text: "hello".to_string(),
+    };
+    let bytes = note.to_bytes().unwrap();
+    let note = Note::<String>::decode::<json::Encoder>(&bytes).unwrap();
+    assert_eq!(note.text.as_str(), "hello");
+    assert!(Note::<String>::decode::<bincode::Encoder>(&bytes).is_err());
+}
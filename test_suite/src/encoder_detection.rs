@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::encoder::{bincode, json};
+use serde_flow::error::SerdeFlowError;
+use serde_flow::flow::header::Header;
+use serde_flow::flow::{Bytes, File, FileAsync};
+use serde_flow::Flow;
+use tempfile::tempdir;
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 2, file(blocking, nonblocking), bytes)]
+#[variants(ConfigV1)]
+pub struct Config {
+    pub name: String,
+    pub retries: u32,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file(blocking, nonblocking), bytes)]
+pub struct ConfigV1 {
+    pub name: String,
+}
+
+impl From<ConfigV1> for Config {
+    fn from(value: ConfigV1) -> Self {
+        Config {
+            name: value.name,
+            retries: 3,
+        }
+    }
+}
+
+#[test]
+fn test_load_detects_encoder() {
+    let temp_dir = tempdir().unwrap();
+    let json_path = temp_dir.path().to_path_buf().join("config.json");
+    let bincode_path = temp_dir.path().to_path_buf().join("config.bin");
+
+    let config = Config {
+        name: "json".to_string(),
+        retries: 1,
+    };
+    config
+        .save_to_path::<json::Encoder>(json_path.as_path())
+        .unwrap();
+    let config = Config {
+        name: "bincode".to_string(),
+        retries: 2,
+    };
+    config
+        .save_to_path::<bincode::Encoder>(bincode_path.as_path())
+        .unwrap();
+
+    let config = Config::load_from_path_auto(json_path.as_path()).unwrap();
+    assert_eq!(config.name.as_str(), "json");
+    assert_eq!(config.retries, 1);
+
+    let config = Config::load_from_path_auto(bincode_path.as_path()).unwrap();
+    assert_eq!(config.name.as_str(), "bincode");
+    assert_eq!(config.retries, 2);
+}
+
+#[test]
+fn test_load_detects_encoder_of_old_variant() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("config");
+
+    let config = ConfigV1 {
+        name: "old".to_string(),
+    };
+    config
+        .save_to_path::<json::Encoder>(path.as_path())
+        .unwrap();
+
+    let config = Config::load_from_path_auto(path.as_path()).unwrap();
+    assert_eq!(config.name.as_str(), "old");
+    assert_eq!(config.retries, 3);
+}
+
+#[tokio::test]
+async fn test_load_async_detects_encoder() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("config");
+
+    let config = ConfigV1 {
+        name: "async".to_string(),
+    };
+    config
+        .save_to_path_async::<bincode::Encoder>(path.as_path())
+        .await
+        .unwrap();
+
+    let config = Config::load_from_path_auto_async(path.as_path())
+        .await
+        .unwrap();
+    assert_eq!(config.name.as_str(), "async");
+}
+
+#[test]
+fn test_decode_detects_encoder() {
+    let config = Config {
+        name: "bytes".to_string(),
+        retries: 5,
+    };
+    let json_bytes = config.encode::<json::Encoder>().unwrap();
+    let bincode_bytes = config.encode::<bincode::Encoder>().unwrap();
+
+    assert_eq!(Config::decode_auto(&json_bytes).unwrap().retries, 5);
+    assert_eq!(Config::decode_auto(&bincode_bytes).unwrap().retries, 5);
+}
+
+#[test]
+fn test_load_missing_file_returns_error() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("missing");
+
+    let result = Config::load_from_path_auto(path.as_path());
+    assert!(matches!(
+        result.as_ref().map_err(SerdeFlowError::without_path),
+        Err(SerdeFlowError::FileNotFound)
+    ));
+}
+
+#[test]
+fn test_decode_unknown_encoder_returns_error() {
+    let mut bytes = Config {
+        name: "unknown".to_string(),
+        retries: 0,
+    }
+    .encode::<bincode::Encoder>()
+    .unwrap();
+    let header = Header {
+        encoder: 200,
+        ..Header::parse(&bytes).unwrap()
+    };
+    header.write_to(&mut bytes);
+
+    let result = Config::decode_auto(&bytes);
+    assert!(matches!(
+        result,
+        Err(SerdeFlowError::EncoderUnsupported(200))
+    ));
+}
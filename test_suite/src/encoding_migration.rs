@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::encoder::{bincode, json, FlowEncoder};
+use serde_flow::error::SerdeFlowError;
+use serde_flow::flow::header::Header;
+use serde_flow::flow::{File, FileAsync, FileMigrate, FileMigrateAsync};
+use serde_flow::Flow;
+use tempfile::tempdir;
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 2, file(blocking, nonblocking))]
+#[variants(ProfileV1)]
+pub struct Profile {
+    pub name: String,
+    pub verified: bool,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file(blocking, nonblocking))]
+pub struct ProfileV1 {
+    pub name: String,
+}
+
+impl From<ProfileV1> for Profile {
+    fn from(value: ProfileV1) -> Self {
+        Profile {
+            name: value.name,
+            verified: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 2, file(backup))]
+#[variants(ProfileV1)]
+pub struct BackedUpProfile {
+    pub name: String,
+}
+
+impl From<ProfileV1> for BackedUpProfile {
+    fn from(value: ProfileV1) -> Self {
+        BackedUpProfile { name: value.name }
+    }
+}
+
+fn read_header(path: &std::path::Path) -> Header {
+    Header::parse(&std::fs::read(path).unwrap()).unwrap()
+}
+
+#[test]
+fn test_migrate_encoding_of_old_variant() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("profile");
+
+    let profile = ProfileV1 {
+        name: "Ann".to_string(),
+    };
+    profile
+        .save_to_path::<json::Encoder>(path.as_path())
+        .unwrap();
+
+    Profile::migrate_encoding::<json::Encoder, bincode::Encoder>(path.as_path()).unwrap();
+
+    let header = read_header(path.as_path());
+    assert_eq!(header.encoder, bincode::Encoder::ID);
+    assert_eq!(header.variant, 2);
+
+    let profile = Profile::load_from_path::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(profile.name.as_str(), "Ann");
+    assert!(!profile.verified);
+}
+
+#[test]
+fn test_migrate_encoding_of_current_variant() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("profile");
+
+    let profile = Profile {
+        name: "Bob".to_string(),
+        verified: true,
+    };
+    profile
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    Profile::migrate_encoding::<bincode::Encoder, json::Encoder>(path.as_path()).unwrap();
+
+    assert_eq!(read_header(path.as_path()).encoder, json::Encoder::ID);
+    let profile = Profile::load_from_path::<json::Encoder>(path.as_path()).unwrap();
+    assert!(profile.verified);
+}
+
+#[test]
+fn test_migrate_encoding_with_wrong_source_returns_error() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("profile");
+
+    let profile = ProfileV1 {
+        name: "Cid".to_string(),
+    };
+    profile
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    let result = Profile::migrate_encoding::<json::Encoder, bincode::Encoder>(path.as_path());
+    assert!(matches!(
+        result.as_ref().map_err(SerdeFlowError::without_path),
+        Err(SerdeFlowError::EncoderMismatch { .. })
+    ));
+    // the file is left untouched
+    assert_eq!(read_header(path.as_path()).variant, 1);
+}
+
+#[test]
+fn test_migrate_encoding_keeps_backup() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("profile");
+
+    let profile = ProfileV1 {
+        name: "Dan".to_string(),
+    };
+    profile
+        .save_to_path::<json::Encoder>(path.as_path())
+        .unwrap();
+
+    BackedUpProfile::migrate_encoding::<json::Encoder, bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(read_header(path.as_path()).encoder, bincode::Encoder::ID);
+
+    let variant = BackedUpProfile::rollback(path.as_path()).unwrap();
+    assert_eq!(variant, 1);
+    let profile = ProfileV1::load_from_path::<json::Encoder>(path.as_path()).unwrap();
+    assert_eq!(profile.name.as_str(), "Dan");
+}
+
+#[test]
+fn test_migrate_encoding_twice_leaves_file_and_backup_untouched() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("profile");
+
+    let profile = ProfileV1 {
+        name: "Fay".to_string(),
+    };
+    profile
+        .save_to_path::<json::Encoder>(path.as_path())
+        .unwrap();
+
+    BackedUpProfile::migrate_encoding::<json::Encoder, bincode::Encoder>(path.as_path()).unwrap();
+    let backup_path = serde_flow::fs::backup_path(path.as_path(), 1);
+    let migrated = std::fs::read(path.as_path()).unwrap();
+    let backup = std::fs::read(backup_path.as_path()).unwrap();
+
+    BackedUpProfile::migrate_encoding::<json::Encoder, bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(std::fs::read(path.as_path()).unwrap(), migrated);
+    assert_eq!(std::fs::read(backup_path.as_path()).unwrap(), backup);
+}
+
+#[tokio::test]
+async fn test_migrate_encoding_async() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("profile");
+
+    let profile = ProfileV1 {
+        name: "Eve".to_string(),
+    };
+    profile
+        .save_to_path_async::<json::Encoder>(path.as_path())
+        .await
+        .unwrap();
+
+    Profile::migrate_encoding_async::<json::Encoder, bincode::Encoder>(path.as_path())
+        .await
+        .unwrap();
+    // the second run finds the file already migrated
+    Profile::migrate_encoding_async::<json::Encoder, bincode::Encoder>(path.as_path())
+        .await
+        .unwrap();
+
+    assert_eq!(read_header(path.as_path()).encoder, bincode::Encoder::ID);
+    let profile = Profile::load_from_path_async::<bincode::Encoder>(path.as_path())
+        .await
+        .unwrap();
+    assert_eq!(profile.name.as_str(), "Eve");
+}
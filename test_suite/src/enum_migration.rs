@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::encoder::{bincode, json};
+use serde_flow::flow::{Bytes, File, FileMigrate};
+use serde_flow::Flow;
+use tempfile::tempdir;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Flow)]
+#[flow(variant = 2, file, bytes)]
+#[variants(JobStateV1)]
+pub enum JobState {
+    Queued,
+    Running { worker: String, progress: u8 },
+    Failed(String, u32),
+    Done,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file, bytes)]
+pub enum JobStateV1 {
+    Pending,
+    InProgress(String),
+    Finished { success: bool },
+}
+
+impl From<JobStateV1> for JobState {
+    fn from(value: JobStateV1) -> Self {
+        match value {
+            JobStateV1::Pending => JobState::Queued,
+            JobStateV1::InProgress(worker) => JobState::Running {
+                worker,
+                progress: 0,
+            },
+            JobStateV1::Finished { success: true } => JobState::Done,
+            JobStateV1::Finished { success: false } => JobState::Failed("unknown".to_string(), 0),
+        }
+    }
+}
+
+#[test]
+fn test_enum_save_and_load() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("job");
+
+    let states = [
+        JobState::Queued,
+        JobState::Running {
+            worker: "w1".to_string(),
+            progress: 50,
+        },
+        JobState::Failed("timeout".to_string(), 3),
+        JobState::Done,
+    ];
+    for state in states {
+        state.save_to_path::<json::Encoder>(path.as_path()).unwrap();
+        let loaded = JobState::load_from_path::<json::Encoder>(path.as_path()).unwrap();
+        assert_eq!(loaded, state);
+    }
+}
+
+#[test]
+fn test_enum_load_and_migrate() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("job");
+
+    let state = JobStateV1::InProgress("w2".to_string());
+    state
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    let state = JobState::load_and_migrate::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(
+        state,
+        JobState::Running {
+            worker: "w2".to_string(),
+            progress: 0,
+        }
+    );
+    let state = JobState::load_from_path::<bincode::Encoder>(path.as_path()).unwrap();
+    assert!(matches!(state, JobState::Running { .. }));
+}
+
+#[test]
+fn test_enum_decode_migration() {
+    let bytes = JobStateV1::Finished { success: false }
+        .encode::<bincode::Encoder>()
+        .unwrap();
+    let state = JobState::decode::<bincode::Encoder>(&bytes).unwrap();
+    assert_eq!(state, JobState::Failed("unknown".to_string(), 0));
+}
+
+mod zerocopy {
+    use rkyv::{Archive, Deserialize, Serialize};
+    use serde_flow::flow::zerocopy::{File, FileMigrate};
+    use serde_flow::Flow;
+    use tempfile::tempdir;
+
+    #[derive(Archive, Serialize, Deserialize, Flow)]
+    #[archive(check_bytes)]
+    #[flow(variant = 2, file, zerocopy)]
+    #[variants(ShapeV1)]
+    pub enum Shape {
+        Circle { radius: f32 },
+        Rect(f32, f32),
+        Empty,
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Flow)]
+    #[archive(check_bytes)]
+    #[flow(variant = 1, file, zerocopy)]
+    pub enum ShapeV1 {
+        Square(f32),
+        Empty,
+    }
+
+    impl From<ShapeV1> for Shape {
+        fn from(value: ShapeV1) -> Self {
+            match value {
+                ShapeV1::Square(side) => Shape::Rect(side, side),
+                ShapeV1::Empty => Shape::Empty,
+            }
+        }
+    }
+
+    #[test]
+    fn test_enum_load_and_migrate() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().to_path_buf().join("shape");
+
+        ShapeV1::Square(2.0).save_to_path(path.as_path()).unwrap();
+
+        let reader = Shape::load_and_migrate(path.as_path()).unwrap();
+        let shape = reader.archive();
+        assert!(matches!(shape, ArchivedShape::Rect(w, h) if *w == 2.0 && *h == 2.0));
+
+        Shape::Circle { radius: 1.5 }
+            .save_to_path(path.as_path())
+            .unwrap();
+        let reader = Shape::load_from_path(path.as_path()).unwrap();
+        let shape = reader.archive();
+        assert!(matches!(shape, ArchivedShape::Circle { radius } if *radius == 1.5));
+    }
+}
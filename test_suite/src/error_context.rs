@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::encoder::{bincode, json, FlowEncoder};
+use serde_flow::error::SerdeFlowError;
+use serde_flow::flow::header::Header;
+use serde_flow::flow::{Bytes, File, FileMigrate};
+use serde_flow::Flow;
+use std::error::Error;
+use tempfile::tempdir;
+
+#[derive(Debug, Serialize, Deserialize, Flow)]
+#[flow(variant = 2, file, bytes)]
+#[variants(GadgetV1)]
+pub struct Gadget {
+    pub name: String,
+    pub weight: u32,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file, bytes)]
+pub struct GadgetV1 {
+    pub name: String,
+}
+
+impl From<GadgetV1> for Gadget {
+    fn from(value: GadgetV1) -> Self {
+        Gadget {
+            name: value.name,
+            weight: 0,
+        }
+    }
+}
+
+/// A release of the gadget newer than the one above.
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 3, file, bytes)]
+pub struct GadgetV3 {
+    pub name: String,
+    pub weight: u32,
+    pub color: String,
+}
+
+/// A type the gadget can't be migrated from.
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 0, bytes)]
+pub struct Widget {
+    pub name: String,
+}
+
+/// Json bytes of the gadget with a payload of another shape and a valid checksum.
+fn invalid_json_bytes() -> Vec<u8> {
+    Header::new(json::Encoder::ID, 2).with_payload(b"{\"name\": 7}")
+}
+
+#[test]
+fn test_decode_error_keeps_source() {
+    let result = Gadget::decode::<json::Encoder>(&invalid_json_bytes());
+    let Err(SerdeFlowError::Decode {
+        encoder, type_name, ..
+    }) = &result
+    else {
+        panic!("decode of invalid json, must return Decode");
+    };
+    assert_eq!(*encoder, json::Encoder::ID);
+    assert_eq!(*type_name, std::any::type_name::<Gadget>());
+
+    let error = result.unwrap_err();
+    let source = error.source().expect("the error of serde_json is kept");
+    assert!(source.downcast_ref::<serde_json::Error>().is_some());
+    // the source is printed by the reporter of the error chain, not by the error itself
+    assert!(!error.to_string().contains(source.to_string().as_str()));
+}
+
+#[test]
+fn test_bincode_decode_error_keeps_source() {
+    let bytes = Header::new(bincode::Encoder::ID, 2).with_payload(&[1, 2, 3]);
+    let error = Gadget::decode::<bincode::Encoder>(&bytes).unwrap_err();
+
+    assert!(matches!(error, SerdeFlowError::Decode { encoder: 2, .. }));
+    let source = error.source().unwrap();
+    assert!(source.downcast_ref::<::bincode::ErrorKind>().is_some());
+}
+
+#[test]
+fn test_file_error_has_path() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("gadget");
+    std::fs::write(path.as_path(), invalid_json_bytes()).unwrap();
+
+    let error = Gadget::load_from_path::<json::Encoder>(path.as_path()).unwrap_err();
+    let SerdeFlowError::File {
+        path: error_path,
+        source,
+    } = &error
+    else {
+        panic!("load of invalid json, must return File");
+    };
+    assert_eq!(error_path, &path);
+    assert!(matches!(**source, SerdeFlowError::Decode { .. }));
+
+    // the path, then the type and the reason in the sources
+    let message = error.to_string();
+    assert!(message.contains(path.display().to_string().as_str()));
+    let source = error.source().unwrap();
+    assert!(source.to_string().contains(std::any::type_name::<Gadget>()));
+    assert!(source.source().is_some());
+}
+
+#[test]
+fn test_nested_file_error_has_one_path() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("gadget");
+    std::fs::write(path.as_path(), invalid_json_bytes()).unwrap();
+
+    let error = Gadget::migrate::<json::Encoder>(path.as_path()).unwrap_err();
+    let message = error.to_string();
+    assert_eq!(
+        message.matches(path.display().to_string().as_str()).count(),
+        1
+    );
+    assert!(matches!(
+        error.without_path(),
+        SerdeFlowError::Decode { .. }
+    ));
+}
+
+#[test]
+fn test_newer_variant_is_unsupported() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("gadget");
+    let gadget = GadgetV3 {
+        name: "lamp".to_string(),
+        weight: 3,
+        color: "red".to_string(),
+    };
+    gadget
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    let error = Gadget::load_from_path::<bincode::Encoder>(path.as_path()).unwrap_err();
+    assert!(matches!(
+        error.without_path(),
+        SerdeFlowError::VariantUnsupported {
+            variant: 3,
+            latest: 2,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_unlisted_variant_is_unknown() {
+    let bytes = Widget {
+        name: "lamp".to_string(),
+    }
+    .encode::<bincode::Encoder>()
+    .unwrap();
+
+    let result = Gadget::decode::<bincode::Encoder>(&bytes);
+    let Err(SerdeFlowError::VariantUnknown { type_name, variant }) = result else {
+        panic!("decode of an unlisted variant, must return VariantUnknown");
+    };
+    assert_eq!(type_name, std::any::type_name::<Gadget>());
+    assert_eq!(variant, 0);
+}
+
+#[test]
+fn test_errors_without_path_are_unchanged() {
+    let error = SerdeFlowError::FormatInvalid;
+    assert!(matches!(
+        error.without_path(),
+        SerdeFlowError::FormatInvalid
+    ));
+}
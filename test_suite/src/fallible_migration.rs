@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::encoder::{bincode, json};
+use serde_flow::error::SerdeFlowError;
+use serde_flow::flow::{Bytes, File, FileAsync, FileMigrate};
+use serde_flow::Flow;
+use tempfile::tempdir;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub enum Role {
+    Admin,
+    Member,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 2, file(blocking, nonblocking), bytes)]
+#[variants(AccountV1)]
+pub struct Account {
+    pub role: Role,
+    pub age: u8,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file(blocking, nonblocking), bytes)]
+pub struct AccountV1 {
+    pub role: String,
+    pub age: u32,
+}
+
+#[derive(Debug)]
+pub enum AccountError {
+    UnknownRole(String),
+    AgeOutOfRange(u32),
+}
+
+impl std::fmt::Display for AccountError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccountError::UnknownRole(role) => write!(f, "unknown role {role}"),
+            AccountError::AgeOutOfRange(age) => write!(f, "age {age} is out of range"),
+        }
+    }
+}
+
+impl std::error::Error for AccountError {}
+
+impl TryFrom<AccountV1> for Account {
+    type Error = AccountError;
+
+    fn try_from(value: AccountV1) -> Result<Self, Self::Error> {
+        let role = match value.role.as_str() {
+            "admin" => Role::Admin,
+            "member" => Role::Member,
+            _ => return Err(AccountError::UnknownRole(value.role)),
+        };
+        let age = u8::try_from(value.age).map_err(|_| AccountError::AgeOutOfRange(value.age))?;
+        Ok(Account { role, age })
+    }
+}
+
+fn assert_migration_failed(result: Result<Account, SerdeFlowError>, message: &str) {
+    match result.as_ref().map_err(SerdeFlowError::without_path) {
+        Err(SerdeFlowError::MigrationFailed {
+            from_variant,
+            to_variant,
+            source,
+        }) => {
+            assert_eq!(*from_variant, 1);
+            assert_eq!(*to_variant, 2);
+            assert_eq!(source.to_string().as_str(), message);
+        }
+        _ => panic!("expected MigrationFailed"),
+    }
+}
+
+#[test]
+fn test_try_from_migration_succeeds() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("account");
+
+    let account = AccountV1 {
+        role: "admin".to_string(),
+        age: 30,
+    };
+    account
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    let account = Account::load_and_migrate::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(account.role, Role::Admin);
+    assert_eq!(account.age, 30);
+}
+
+#[test]
+fn test_try_from_migration_fails_on_file() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("account");
+
+    let account = AccountV1 {
+        role: "guest".to_string(),
+        age: 30,
+    };
+    account
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+    let original = std::fs::read(path.as_path()).unwrap();
+
+    let result = Account::load_from_path::<bincode::Encoder>(path.as_path());
+    assert_migration_failed(result, "unknown role guest");
+
+    let result = Account::migrate::<bincode::Encoder>(path.as_path());
+    assert!(matches!(
+        result.as_ref().map_err(SerdeFlowError::without_path),
+        Err(SerdeFlowError::MigrationFailed { .. })
+    ));
+    // the failed migration keeps the file untouched
+    assert_eq!(std::fs::read(path.as_path()).unwrap(), original);
+}
+
+#[test]
+fn test_try_from_migration_fails_on_bytes() {
+    let account = AccountV1 {
+        role: "member".to_string(),
+        age: 300,
+    };
+    let bytes = account.encode::<json::Encoder>().unwrap();
+
+    let result = Account::decode::<json::Encoder>(&bytes);
+    assert_migration_failed(result, "age 300 is out of range");
+}
+
+#[tokio::test]
+async fn test_try_from_migration_fails_async() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("account");
+
+    let account = AccountV1 {
+        role: "guest".to_string(),
+        age: 30,
+    };
+    account
+        .save_to_path_async::<bincode::Encoder>(path.as_path())
+        .await
+        .unwrap();
+
+    let result = Account::load_from_path_async::<bincode::Encoder>(path.as_path()).await;
+    assert_migration_failed(result, "unknown role guest");
+}
+
+mod zerocopy {
+    use rkyv::{Archive, Deserialize, Serialize};
+    use serde_flow::error::SerdeFlowError;
+    use serde_flow::flow::zerocopy::{File, FileMigrate};
+    use serde_flow::Flow;
+    use tempfile::tempdir;
+
+    #[derive(Archive, Serialize, Deserialize, Flow)]
+    #[archive(check_bytes)]
+    #[flow(variant = 2, file, zerocopy)]
+    #[variants(CounterV1)]
+    pub struct Counter {
+        pub value: u16,
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Flow)]
+    #[archive(check_bytes)]
+    #[flow(variant = 1, file, zerocopy)]
+    pub struct CounterV1 {
+        pub value: u64,
+    }
+
+    impl TryFrom<CounterV1> for Counter {
+        type Error = std::num::TryFromIntError;
+
+        fn try_from(value: CounterV1) -> Result<Self, Self::Error> {
+            Ok(Counter {
+                value: u16::try_from(value.value)?,
+            })
+        }
+    }
+
+    #[test]
+    fn test_try_from_migration() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().to_path_buf().join("counter");
+
+        CounterV1 { value: 7 }.save_to_path(path.as_path()).unwrap();
+        let reader = Counter::load_and_migrate(path.as_path()).unwrap();
+        assert_eq!(reader.archive().value, 7);
+
+        CounterV1 { value: 70_000 }
+            .save_to_path(path.as_path())
+            .unwrap();
+        let result = Counter::load_from_path(path.as_path());
+        assert!(matches!(
+            result.as_ref().map_err(SerdeFlowError::without_path),
+            Err(SerdeFlowError::MigrationFailed {
+                from_variant: 1,
+                to_variant: 2,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,39 @@
+use serde_flow::flow::Encoding;
+use serde_flow::FlowInfo;
+
+use crate::chain_migration::{Order, OrderV1, OrderV3};
+use crate::generic_migration::Page;
+use crate::module_variants::{legacy, User};
+use crate::zerocopy_basic_migration::Car;
+
+#[test]
+fn test_variant() {
+    assert_eq!(Order::VARIANT, 4);
+    assert_eq!(OrderV1::VARIANT, 1);
+    assert_eq!(<Page<String> as FlowInfo>::VARIANT, 2);
+}
+
+#[test]
+fn test_accepted_variants_follow_chains() {
+    assert_eq!(OrderV1::ACCEPTED_VARIANTS, &[1]);
+    assert_eq!(OrderV3::ACCEPTED_VARIANTS, &[3, 2, 1]);
+    assert_eq!(Order::ACCEPTED_VARIANTS, &[4, 3, 2, 1]);
+    assert_eq!(<Page<u8> as FlowInfo>::ACCEPTED_VARIANTS, &[2, 1]);
+    assert_eq!(User::ACCEPTED_VARIANTS, &[3, 1, 2]);
+}
+
+#[test]
+fn test_type_name_contains_module_path() {
+    assert_eq!(Order::TYPE_NAME, "test_suite::chain_migration::Order");
+    assert_eq!(
+        legacy::User::TYPE_NAME,
+        "test_suite::module_variants::legacy::User"
+    );
+    assert_ne!(legacy::User::TYPE_NAME, User::TYPE_NAME);
+}
+
+#[test]
+fn test_encoding() {
+    assert_eq!(Order::ENCODING, Encoding::Serde);
+    assert_eq!(Car::ENCODING, Encoding::Zerocopy);
+}
@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::encoder::{bincode, json};
+use serde_flow::flow::{Bytes, File, FileAsync, FileMigrate, FileMigrateAsync};
+use serde_flow::Flow;
+use std::borrow::Cow;
+use std::fmt::Debug;
+use tempfile::tempdir;
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 2, file(blocking, nonblocking), bytes)]
+#[variants(PageV1<T>)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: usize,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file(blocking, nonblocking), bytes)]
+pub struct PageV1<T> {
+    pub items: Vec<T>,
+}
+
+impl<T> From<PageV1<T>> for Page<T> {
+    fn from(value: PageV1<T>) -> Self {
+        Page {
+            total: value.items.len(),
+            items: value.items,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, bytes)]
+pub struct Tagged<K, V>
+where
+    K: Ord + Debug,
+{
+    pub key: K,
+    pub value: V,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file)]
+pub struct Label<'a> {
+    pub text: Cow<'a, str>,
+}
+
+#[test]
+fn test_generic_load_and_migrate() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("page");
+
+    let page = PageV1 {
+        items: vec!["a".to_string(), "b".to_string()],
+    };
+    page.save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    let page = Page::<String>::load_and_migrate::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(page.total, 2);
+    assert_eq!(page.items, vec!["a".to_string(), "b".to_string()]);
+
+    let page = Page::<String>::load_from_path::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(page.total, 2);
+}
+
+#[tokio::test]
+async fn test_generic_load_and_migrate_async() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("page");
+
+    let page = PageV1 {
+        items: vec![1u32, 2, 3],
+    };
+    page.save_to_path_async::<json::Encoder>(path.as_path())
+        .await
+        .unwrap();
+
+    let page = Page::<u32>::load_and_migrate_async::<json::Encoder>(path.as_path())
+        .await
+        .unwrap();
+    assert_eq!(page.total, 3);
+}
+
+#[test]
+fn test_generic_bytes() {
+    let page = PageV1 {
+        items: vec![1u8, 2],
+    };
+    let bytes = page.encode::<bincode::Encoder>().unwrap();
+    let page = Page::<u8>::decode::<bincode::Encoder>(&bytes).unwrap();
+    assert_eq!(page.items, vec![1, 2]);
+
+    let tagged = Tagged {
+        key: 7u64,
+        value: "seven".to_string(),
+    };
+    let bytes = tagged.encode::<json::Encoder>().unwrap();
+    let tagged = Tagged::<u64, String>::decode::<json::Encoder>(&bytes).unwrap();
+    assert_eq!(tagged.key, 7);
+    assert_eq!(tagged.value.as_str(), "seven");
+}
+
+#[test]
+fn test_lifetime_save_and_load() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("label");
+
+    let text = "borrowed".to_string();
+    let label = Label {
+        text: Cow::Borrowed(text.as_str()),
+    };
+    label
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    let label = Label::load_from_path::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(label.text, "borrowed");
+}
+
+mod zerocopy {
+    use rkyv::{Archive, Deserialize, Serialize};
+    use serde_flow::flow::zerocopy::{File, FileMigrate};
+    use serde_flow::Flow;
+    use tempfile::tempdir;
+
+    #[derive(Archive, Serialize, Deserialize, Flow)]
+    #[archive(check_bytes)]
+    #[flow(variant = 2, file, zerocopy)]
+    #[variants(PairV1<T>)]
+    pub struct Pair<T> {
+        pub left: T,
+        pub right: T,
+    }
+
+    #[derive(Archive, Serialize, Deserialize, Flow)]
+    #[archive(check_bytes)]
+    #[flow(variant = 1, file, zerocopy)]
+    pub struct PairV1<T> {
+        pub left: T,
+    }
+
+    impl<T: Clone> From<PairV1<T>> for Pair<T> {
+        fn from(value: PairV1<T>) -> Self {
+            Pair {
+                right: value.left.clone(),
+                left: value.left,
+            }
+        }
+    }
+
+    #[test]
+    fn test_generic_load_and_migrate() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().to_path_buf().join("pair");
+
+        PairV1 { left: 5u32 }.save_to_path(path.as_path()).unwrap();
+
+        let reader = Pair::<u32>::load_and_migrate(path.as_path()).unwrap();
+        let pair = reader.archive();
+        assert_eq!(pair.left, 5);
+        assert_eq!(pair.right, 5);
+    }
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::encoder::{bincode, json, FlowEncoder};
+use serde_flow::error::SerdeFlowError;
+use serde_flow::flow::header::{Header, HEADER_SIZE, MAGIC};
+use serde_flow::flow::{Bytes, File};
+use serde_flow::Flow;
+use tempfile::tempdir;
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 7, file, bytes)]
+pub struct Document {
+    pub title: String,
+}
+
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Flow)]
+#[archive(check_bytes)]
+#[flow(variant = 7, file, zerocopy)]
+pub struct ArchivedDocument {
+    pub title: String,
+}
+
+#[test]
+fn test_file_starts_with_header() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("document");
+
+    let document = Document {
+        title: "Header".to_string(),
+    };
+    document
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    let bytes = std::fs::read(path.as_path()).unwrap();
+    assert_eq!(bytes[0..4], MAGIC);
+
+    let (header, payload) = Header::split(&bytes).unwrap();
+    assert_eq!(header.encoder, bincode::Encoder::ID);
+    assert_eq!(header.variant, 7);
+    assert_eq!(payload.len(), bytes.len() - HEADER_SIZE);
+}
+
+#[test]
+fn test_zerocopy_file_starts_with_header() {
+    use serde_flow::flow::zerocopy::File;
+
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("document");
+
+    let document = ArchivedDocument {
+        title: "Header".to_string(),
+    };
+    document.save_to_path(path.as_path()).unwrap();
+
+    let bytes = std::fs::read(path.as_path()).unwrap();
+    let header = Header::parse(&bytes).unwrap();
+    assert_eq!(header.encoder, serde_flow::encoder::zerocopy::Encoder::ID);
+    assert_eq!(header.variant, 7);
+}
+
+#[test]
+fn test_load_with_other_encoder_returns_error() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("document");
+
+    let document = Document {
+        title: "Header".to_string(),
+    };
+    document
+        .save_to_path::<json::Encoder>(path.as_path())
+        .unwrap();
+
+    let result = Document::load_from_path::<bincode::Encoder>(path.as_path());
+    let Err(SerdeFlowError::EncoderMismatch { expected, found }) =
+        result.as_ref().map_err(SerdeFlowError::without_path)
+    else {
+        panic!("load_from_path with other encoder, must return EncoderMismatch");
+    };
+    assert_eq!(*expected, bincode::Encoder::ID);
+    assert_eq!(*found, json::Encoder::ID);
+}
+
+#[test]
+fn test_load_zerocopy_file_as_serde_returns_error() {
+    use serde_flow::flow::zerocopy::File;
+
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("document");
+
+    let document = ArchivedDocument {
+        title: "Header".to_string(),
+    };
+    document.save_to_path(path.as_path()).unwrap();
+
+    let result = Document::load_from_path::<bincode::Encoder>(path.as_path());
+    assert!(matches!(
+        result.as_ref().map_err(SerdeFlowError::without_path),
+        Err(SerdeFlowError::EncoderMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_decode_without_magic_returns_error() {
+    // too short for the header and for the variant of the layout written before it
+    let result = Document::decode::<bincode::Encoder>(&[7]);
+    assert!(matches!(result, Err(SerdeFlowError::FormatInvalid)));
+}
+
+#[test]
+fn test_decode_newer_header_returns_error() {
+    let document = Document {
+        title: "Header".to_string(),
+    };
+    let mut bytes = document.encode::<bincode::Encoder>().unwrap();
+    bytes[4] = 200;
+
+    let result = Document::decode::<bincode::Encoder>(&bytes);
+    assert!(matches!(
+        result,
+        Err(SerdeFlowError::HeaderVersionUnsupported(200))
+    ));
+}
+
+#[test]
+fn test_encode_writes_payload_behind_header() {
+    let document = Document {
+        title: "In place".to_string(),
+    };
+
+    for (bytes, payload) in [
+        (
+            document.encode::<bincode::Encoder>().unwrap(),
+            bincode::Encoder::serialize(&document).unwrap(),
+        ),
+        (
+            document.encode::<json::Encoder>().unwrap(),
+            json::Encoder::serialize(&document).unwrap(),
+        ),
+    ] {
+        let (header, written) = Header::split(&bytes).unwrap();
+        assert_eq!(written, payload.as_slice());
+        assert_eq!(
+            header.to_bytes(),
+            Header::split(&Header::new(header.encoder, 7).with_payload(&payload))
+                .unwrap()
+                .0
+                .to_bytes()
+        );
+        header.verify(written).unwrap();
+    }
+}
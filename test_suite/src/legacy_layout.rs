@@ -0,0 +1,268 @@
+use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
+use serde::{Deserialize, Serialize};
+use serde_flow::encoder::{bincode, json, zerocopy, FlowEncoder};
+use serde_flow::flow::header::{self, Header};
+use serde_flow::flow::{Bytes, File, FileMigrate};
+use serde_flow::Flow;
+use tempfile::tempdir;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Flow)]
+#[flow(variant = 2, file, bytes)]
+#[variants(NoteV1)]
+pub struct Note {
+    pub title: String,
+    pub pinned: bool,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file, bytes)]
+pub struct NoteV1 {
+    pub title: String,
+}
+
+impl From<NoteV1> for Note {
+    fn from(value: NoteV1) -> Self {
+        Note {
+            title: value.title,
+            pinned: false,
+        }
+    }
+}
+
+/// The payload of `Note` written by releases before the header, the variant is its first field.
+#[derive(Serialize)]
+struct LegacyNote {
+    flow_id: u16,
+    title: String,
+    pinned: bool,
+}
+
+/// The payload of `NoteV1` written by releases before the header.
+#[derive(Serialize)]
+struct LegacyNoteV1 {
+    flow_id: u16,
+    title: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Flow)]
+#[flow(variant = 1, bytes)]
+#[serde(deny_unknown_fields)]
+pub struct Tag {
+    pub label: String,
+}
+
+#[derive(Debug, PartialEq, Archive, RkyvSerialize, RkyvDeserialize, Flow)]
+#[archive(check_bytes)]
+#[flow(variant = 2, file, bytes, zerocopy)]
+#[variants(SketchV1)]
+pub struct Sketch {
+    pub name: String,
+    pub strokes: Vec<u32>,
+}
+
+#[derive(Archive, RkyvSerialize, RkyvDeserialize, Flow)]
+#[archive(check_bytes)]
+#[flow(variant = 1, file, zerocopy)]
+pub struct SketchV1 {
+    pub name: String,
+}
+
+impl From<SketchV1> for Sketch {
+    fn from(value: SketchV1) -> Self {
+        Sketch {
+            name: value.name,
+            strokes: Vec::new(),
+        }
+    }
+}
+
+/// The zerocopy layout written by releases before the header: the variant followed by the archive.
+fn legacy_archive<T>(variant: u16, value: &T) -> Vec<u8>
+where
+    T: rkyv::Archive + rkyv::Serialize<zerocopy::DefaultSerializer>,
+{
+    let mut bytes = variant.to_le_bytes().to_vec();
+    bytes.extend_from_slice(&zerocopy::Encoder::serialize(value).unwrap());
+    bytes
+}
+
+fn note() -> Note {
+    Note {
+        title: "groceries".to_string(),
+        pinned: true,
+    }
+}
+
+fn legacy_note() -> LegacyNote {
+    LegacyNote {
+        flow_id: 2,
+        title: "groceries".to_string(),
+        pinned: true,
+    }
+}
+
+#[test]
+fn test_load_legacy_bincode_file() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("note");
+    std::fs::write(
+        path.as_path(),
+        ::bincode::serialize(&legacy_note()).unwrap(),
+    )
+    .unwrap();
+
+    let loaded = Note::load_from_path::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(loaded, note());
+}
+
+#[test]
+fn test_decode_legacy_json_bytes() {
+    let bytes = serde_json::to_vec(&legacy_note()).unwrap();
+    assert_eq!(Note::decode::<json::Encoder>(&bytes).unwrap(), note());
+}
+
+#[test]
+fn test_decode_legacy_json_bytes_denying_unknown_fields() {
+    let bytes = br#"{"flow_id":1,"label":"work"}"#;
+    let expected = Tag {
+        label: "work".to_string(),
+    };
+    assert_eq!(Tag::decode::<json::Encoder>(bytes).unwrap(), expected);
+    assert_eq!(Tag::decode_auto(bytes).unwrap(), expected);
+}
+
+#[test]
+fn test_decode_legacy_older_variant() {
+    let legacy = LegacyNoteV1 {
+        flow_id: 1,
+        title: "groceries".to_string(),
+    };
+
+    let bytes = ::bincode::serialize(&legacy).unwrap();
+    let decoded = Note::decode::<bincode::Encoder>(&bytes).unwrap();
+    assert_eq!(decoded.title, "groceries");
+    assert!(!decoded.pinned);
+
+    let bytes = serde_json::to_vec(&legacy).unwrap();
+    assert!(!Note::decode::<json::Encoder>(&bytes).unwrap().pinned);
+}
+
+#[test]
+fn test_auto_load_legacy_json_file() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("note");
+    std::fs::write(
+        path.as_path(),
+        serde_json::to_vec_pretty(&legacy_note()).unwrap(),
+    )
+    .unwrap();
+
+    let loaded = Note::load_from_path_auto(path.as_path()).unwrap();
+    assert_eq!(loaded, note());
+}
+
+#[test]
+fn test_decode_auto_legacy_bytes() {
+    let bytes = ::bincode::serialize(&legacy_note()).unwrap();
+    assert_eq!(Note::decode_auto(&bytes).unwrap(), note());
+
+    let legacy = LegacyNoteV1 {
+        flow_id: 1,
+        title: "groceries".to_string(),
+    };
+    let bytes = serde_json::to_vec(&legacy).unwrap();
+    assert!(!Note::decode_auto(&bytes).unwrap().pinned);
+}
+
+#[test]
+fn test_migrate_rewrites_legacy_file_with_header() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("note");
+    std::fs::write(
+        path.as_path(),
+        ::bincode::serialize(&legacy_note()).unwrap(),
+    )
+    .unwrap();
+
+    // the file of the current variant is written again, only its layout is old
+    Note::migrate::<bincode::Encoder>(path.as_path()).unwrap();
+
+    let bytes = std::fs::read(path.as_path()).unwrap();
+    assert!(!header::is_legacy(&bytes));
+    let header = Header::parse(&bytes).unwrap();
+    assert_eq!(header.encoder, bincode::Encoder::ID);
+    assert_eq!(header.variant, 2);
+    assert_eq!(
+        Note::load_from_path::<bincode::Encoder>(path.as_path()).unwrap(),
+        note()
+    );
+}
+
+#[test]
+fn test_save_replaces_legacy_file_with_header() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("note");
+    std::fs::write(
+        path.as_path(),
+        ::bincode::serialize(&legacy_note()).unwrap(),
+    )
+    .unwrap();
+
+    let loaded = Note::load_from_path::<bincode::Encoder>(path.as_path()).unwrap();
+    loaded
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    let bytes = std::fs::read(path.as_path()).unwrap();
+    assert_eq!(Header::parse(&bytes).unwrap().variant, 2);
+}
+
+#[test]
+fn test_load_legacy_zerocopy_file_rewrites_it() {
+    use serde_flow::flow::zerocopy::File;
+
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("sketch");
+    let sketch = Sketch {
+        name: "tree".to_string(),
+        strokes: vec![3, 5, 8],
+    };
+    std::fs::write(path.as_path(), legacy_archive(2, &sketch)).unwrap();
+
+    let reader = Sketch::load_from_path(path.as_path()).unwrap();
+    assert_eq!(reader.deserialize().unwrap(), sketch);
+
+    let bytes = std::fs::read(path.as_path()).unwrap();
+    let header = Header::parse(&bytes).unwrap();
+    assert_eq!(header.encoder, zerocopy::Encoder::ID);
+    assert_eq!(header.variant, 2);
+}
+
+#[test]
+fn test_load_legacy_zerocopy_older_variant() {
+    use serde_flow::flow::zerocopy::File;
+
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("sketch");
+    let sketch = SketchV1 {
+        name: "tree".to_string(),
+    };
+    std::fs::write(path.as_path(), legacy_archive(1, &sketch)).unwrap();
+
+    let reader = Sketch::load_from_path_mmap(path.as_path()).unwrap();
+    assert_eq!(reader.name, "tree");
+    assert!(reader.strokes.is_empty());
+    assert!(!header::is_legacy(&std::fs::read(path.as_path()).unwrap()));
+}
+
+#[test]
+fn test_decode_legacy_zerocopy_bytes() {
+    use serde_flow::flow::zerocopy::Bytes;
+
+    let sketch = Sketch {
+        name: "tree".to_string(),
+        strokes: vec![1],
+    };
+    let reader = Sketch::decode(legacy_archive(2, &sketch)).unwrap();
+    assert_eq!(reader.deserialize().unwrap(), sketch);
+}
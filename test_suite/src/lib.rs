@@ -15,3 +15,72 @@ pub mod json_migration;
 
 #[cfg(test)]
 pub mod bytes_migration;
+
+#[cfg(test)]
+pub mod header;
+
+#[cfg(test)]
+pub mod checksum;
+
+#[cfg(test)]
+pub mod atomic_write;
+
+#[cfg(test)]
+pub mod backup_migration;
+
+#[cfg(test)]
+pub mod chain_migration;
+
+#[cfg(test)]
+pub mod fallible_migration;
+
+#[cfg(test)]
+pub mod enum_migration;
+
+#[cfg(test)]
+pub mod tuple_migration;
+
+#[cfg(test)]
+pub mod generic_migration;
+
+#[cfg(test)]
+pub mod module_variants;
+
+#[cfg(test)]
+pub mod flow_info;
+
+#[cfg(test)]
+pub mod default_encoder;
+
+#[cfg(test)]
+pub mod encoder_detection;
+
+#[cfg(test)]
+pub mod encoding_migration;
+
+#[cfg(test)]
+pub mod cross_family_migration;
+
+#[cfg(test)]
+pub mod configured_encoder;
+
+#[cfg(test)]
+pub mod tracing_events;
+
+#[cfg(test)]
+pub mod error_context;
+
+#[cfg(test)]
+pub mod mmap_loading;
+
+#[cfg(test)]
+pub mod zerocopy_alignment;
+
+#[cfg(test)]
+pub mod zerocopy_reader;
+
+#[cfg(test)]
+pub mod borrowed_decode;
+
+#[cfg(test)]
+pub mod legacy_layout;
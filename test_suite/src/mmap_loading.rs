@@ -0,0 +1,163 @@
+use rkyv::{Archive, Deserialize, Serialize};
+use serde_flow::encoder::zerocopy::{self, MmapReader};
+use serde_flow::error::SerdeFlowError;
+use serde_flow::flow::header::Header;
+use serde_flow::flow::zerocopy::File;
+use serde_flow::Flow;
+use std::sync::Arc;
+use tempfile::tempdir;
+
+#[derive(Debug, PartialEq, Archive, Serialize, Deserialize, Flow)]
+#[archive(check_bytes)]
+#[flow(variant = 2, file, zerocopy)]
+#[variants(TileV1)]
+pub struct Tile {
+    pub name: String,
+    pub pixels: Vec<u32>,
+}
+
+#[derive(Archive, Serialize, Deserialize, Flow)]
+#[archive(check_bytes)]
+#[flow(variant = 1, file, zerocopy)]
+pub struct TileV1 {
+    pub name: String,
+}
+
+impl From<TileV1> for Tile {
+    fn from(value: TileV1) -> Self {
+        Tile {
+            name: value.name,
+            pixels: Vec::new(),
+        }
+    }
+}
+
+fn tile() -> Tile {
+    Tile {
+        name: "forest".to_string(),
+        pixels: (0..1024).collect(),
+    }
+}
+
+#[test]
+fn test_load_from_path_mmap() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("tile");
+    tile().save_to_path(path.as_path()).unwrap();
+
+    let reader = Tile::load_from_path_mmap(path.as_path()).unwrap();
+    assert_eq!(reader.name, "forest");
+    assert_eq!(reader.pixels.len(), 1024);
+    assert_eq!(reader.pixels[1000], 1000);
+    assert_eq!(reader.deserialize().unwrap(), tile());
+}
+
+#[test]
+fn test_load_from_path_mmap_migrates_older_variant() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("tile");
+    TileV1 {
+        name: "desert".to_string(),
+    }
+    .save_to_path(path.as_path())
+    .unwrap();
+
+    let reader = Tile::load_from_path_mmap(path.as_path()).unwrap();
+    assert_eq!(reader.name, "desert");
+    assert!(reader.pixels.is_empty());
+
+    let bytes = std::fs::read(path.as_path()).unwrap();
+    assert_eq!(Header::parse(&bytes).unwrap().variant, 2);
+}
+
+#[test]
+fn test_load_from_path_mmap_corrupted_file_returns_error() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("tile");
+    tile().save_to_path(path.as_path()).unwrap();
+
+    let mut bytes = std::fs::read(path.as_path()).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xFF;
+    std::fs::write(path.as_path(), bytes).unwrap();
+
+    let result = Tile::load_from_path_mmap(path.as_path());
+    assert!(matches!(
+        result.as_ref().map_err(SerdeFlowError::without_path),
+        Err(SerdeFlowError::ChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_load_from_path_mmap_missing_file_returns_error() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("missing");
+
+    let result = Tile::load_from_path_mmap(path.as_path());
+    assert!(matches!(
+        result.as_ref().map_err(SerdeFlowError::without_path),
+        Err(SerdeFlowError::FileNotFound)
+    ));
+}
+
+#[test]
+fn test_mmap_reader_is_shared_between_threads() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("tile");
+    tile().save_to_path(path.as_path()).unwrap();
+
+    let reader: Arc<MmapReader<Tile>> =
+        Arc::new(Tile::load_from_path_mmap(path.as_path()).unwrap());
+    let handles: Vec<_> = (0..4)
+        .map(|index| {
+            let reader = Arc::clone(&reader);
+            std::thread::spawn(move || reader.pixels[index * 100])
+        })
+        .collect();
+    let pixels: Vec<u32> = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+    assert_eq!(pixels, vec![0, 100, 200, 300]);
+}
+
+#[test]
+fn test_mmap_reader_rejects_other_encoders() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("tile");
+    std::fs::write(path.as_path(), Header::new(2, 2).with_payload(b"payload")).unwrap();
+
+    let file = std::fs::File::open(path.as_path()).unwrap();
+    let mmap = unsafe { memmap2::Mmap::map(&file).unwrap() };
+    let result = MmapReader::<Tile>::new(mmap);
+    assert!(matches!(
+        result,
+        Err(SerdeFlowError::EncoderMismatch {
+            expected: zerocopy::Encoder::ID,
+            found: 2
+        })
+    ));
+}
+
+#[test]
+fn test_mmap_reader_rejects_other_variants() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("tile");
+    TileV1 {
+        name: "desert".to_string(),
+    }
+    .save_to_path(path.as_path())
+    .unwrap();
+
+    let file = std::fs::File::open(path.as_path()).unwrap();
+    let mmap = unsafe { memmap2::Mmap::map(&file).unwrap() };
+    let result = MmapReader::<Tile>::new(mmap);
+    assert!(matches!(
+        result,
+        Err(SerdeFlowError::VariantMismatch {
+            expected: 2,
+            found: 1,
+            ..
+        })
+    ));
+}
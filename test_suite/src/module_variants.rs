@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::encoder::bincode;
+use serde_flow::flow::{Bytes, File, FileMigrate};
+use serde_flow::{Flow, FlowInfo};
+use tempfile::tempdir;
+
+pub mod v1 {
+    use serde::{Deserialize, Serialize};
+    use serde_flow::Flow;
+
+    #[derive(Serialize, Deserialize, Flow)]
+    #[flow(variant = 1, file, bytes)]
+    pub struct User {
+        pub name: String,
+    }
+}
+
+pub mod legacy {
+    use serde::{Deserialize, Serialize};
+    use serde_flow::Flow;
+
+    #[derive(Serialize, Deserialize, Flow)]
+    #[flow(variant = 2, file, bytes)]
+    pub struct User {
+        pub first_name: String,
+        pub last_name: String,
+    }
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 3, file, bytes)]
+#[variants(crate::module_variants::v1::User, legacy::User)]
+pub struct User {
+    pub full_name: String,
+}
+
+impl From<v1::User> for User {
+    fn from(value: v1::User) -> Self {
+        User {
+            full_name: value.name,
+        }
+    }
+}
+
+impl From<legacy::User> for User {
+    fn from(value: legacy::User) -> Self {
+        User {
+            full_name: format!("{} {}", value.first_name, value.last_name),
+        }
+    }
+}
+
+#[test]
+fn test_variant_ids_are_associated_consts() {
+    assert_eq!(v1::User::VARIANT, 1);
+    assert_eq!(legacy::User::VARIANT, 2);
+    assert_eq!(<User as FlowInfo>::VARIANT, 3);
+}
+
+#[test]
+fn test_migrate_from_variants_in_other_modules() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("user");
+
+    let user = legacy::User {
+        first_name: "Jan".to_string(),
+        last_name: "Janssen".to_string(),
+    };
+    user.save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+
+    let user = User::load_and_migrate::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(user.full_name.as_str(), "Jan Janssen");
+
+    let bytes = v1::User {
+        name: "Jan".to_string(),
+    }
+    .encode::<bincode::Encoder>()
+    .unwrap();
+    let user = User::decode::<bincode::Encoder>(&bytes).unwrap();
+    assert_eq!(user.full_name.as_str(), "Jan");
+}
@@ -0,0 +1,218 @@
+use rkyv::{Archive, Deserialize, Serialize};
+use serde_flow::encoder::{bincode, json};
+use serde_flow::flow::{Bytes, File, FileAsync, FileMigrate};
+use serde_flow::Flow;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tempfile::tempdir;
+use tracing::subscriber::DefaultGuard;
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, Flow)]
+#[flow(variant = 2, file(blocking, nonblocking), bytes)]
+#[variants(SensorV1)]
+pub struct Sensor {
+    pub name: String,
+    pub unit: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Flow)]
+#[flow(variant = 1, file(blocking, nonblocking), bytes)]
+pub struct SensorV1 {
+    pub name: String,
+}
+
+impl From<SensorV1> for Sensor {
+    fn from(value: SensorV1) -> Self {
+        Sensor {
+            name: value.name,
+            unit: "C".to_string(),
+        }
+    }
+}
+
+#[derive(Archive, Serialize, Deserialize, Flow)]
+#[archive(check_bytes)]
+#[flow(variant = 1, file, zerocopy)]
+pub struct Reading {
+    pub value: u32,
+}
+
+/// Collects the output of the fmt subscriber.
+#[derive(Clone, Default)]
+struct Output(Arc<Mutex<Vec<u8>>>);
+
+impl Output {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Captures the events of the current thread until the guard is dropped.
+fn capture() -> (Output, DefaultGuard) {
+    let output = Output::default();
+    let writer = output.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    (output, tracing::subscriber::set_default(subscriber))
+}
+
+#[test]
+fn test_save_and_load_spans() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("sensor");
+    let sensor = Sensor {
+        name: "boiler".to_string(),
+        unit: "C".to_string(),
+    };
+
+    let (output, guard) = capture();
+    sensor
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+    Sensor::load_from_path::<bincode::Encoder>(path.as_path()).unwrap();
+    drop(guard);
+
+    let text = output.text();
+    let path = path.display().to_string();
+    assert!(text.contains(&format!(
+        "save{{type_name=\"{}\" path={path}}}",
+        std::any::type_name::<Sensor>()
+    )));
+    assert!(text.contains(&format!(
+        "load{{type_name=\"{}\" path={path}}}",
+        std::any::type_name::<Sensor>()
+    )));
+    assert!(text.contains("encode type_name="));
+    assert!(text.contains("decode type_name="));
+    assert!(text.contains("encoder=2 variant=2"));
+    assert!(text.contains("size="));
+    assert!(text.contains("duration_us="));
+}
+
+#[test]
+fn test_auto_load_spans() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("sensor");
+    let sensor = Sensor {
+        name: "boiler".to_string(),
+        unit: "C".to_string(),
+    };
+    sensor
+        .save_to_path::<json::Encoder>(path.as_path())
+        .unwrap();
+
+    let (output, guard) = capture();
+    Sensor::load_from_path_auto(path.as_path()).unwrap();
+    drop(guard);
+
+    let text = output.text();
+    let path = path.display().to_string();
+    assert!(text.contains(&format!(
+        "load{{type_name=\"{}\" path={path}}}: serde_flow: decode",
+        std::any::type_name::<Sensor>()
+    )));
+    assert!(text.contains("encoder=3 variant=2"));
+}
+
+#[test]
+fn test_migrate_reports_variants() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("sensor");
+    let sensor = SensorV1 {
+        name: "boiler".to_string(),
+    };
+    sensor
+        .save_to_path::<json::Encoder>(path.as_path())
+        .unwrap();
+
+    let (output, guard) = capture();
+    Sensor::migrate::<json::Encoder>(path.as_path()).unwrap();
+    drop(guard);
+
+    let text = output.text();
+    assert!(text.contains("migrate{type_name="));
+    assert!(text.contains("from_variant=1 to_variant=2"));
+    assert!(text.contains("encoder=3 variant=1"));
+}
+
+#[test]
+fn test_bytes_events() {
+    let sensor = Sensor {
+        name: "boiler".to_string(),
+        unit: "C".to_string(),
+    };
+
+    let (output, guard) = capture();
+    let bytes = sensor.encode::<bincode::Encoder>().unwrap();
+    Sensor::decode::<bincode::Encoder>(&bytes).unwrap();
+    drop(guard);
+
+    let text = output.text();
+    assert!(text.contains(&format!("size={}", bytes.len())));
+    assert_eq!(text.matches("encoder=2 variant=2").count(), 2);
+}
+
+#[test]
+fn test_zerocopy_events() {
+    use serde_flow::flow::zerocopy::File;
+
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("reading");
+
+    let (output, guard) = capture();
+    Reading { value: 7 }.save_to_path(path.as_path()).unwrap();
+    let reader = Reading::load_from_path(path.as_path()).unwrap();
+    assert_eq!(reader.archive().value, 7);
+    drop(guard);
+
+    let text = output.text();
+    assert!(text.contains("save{type_name="));
+    assert!(text.contains("load{type_name="));
+    assert_eq!(text.matches("encoder=1 variant=1").count(), 2);
+}
+
+#[tokio::test]
+async fn test_async_spans() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("sensor");
+    let sensor = Sensor {
+        name: "boiler".to_string(),
+        unit: "C".to_string(),
+    };
+
+    let (output, guard) = capture();
+    sensor
+        .save_to_path_async::<bincode::Encoder>(path.as_path())
+        .await
+        .unwrap();
+    Sensor::load_from_path_async::<bincode::Encoder>(path.as_path())
+        .await
+        .unwrap();
+    Sensor::load_from_path_auto_async(path.as_path())
+        .await
+        .unwrap();
+    drop(guard);
+
+    let text = output.text();
+    let path = path.display().to_string();
+    assert!(text.contains(&format!("path={path}}}: serde_flow: encode")));
+    assert_eq!(
+        text.matches(&format!("path={path}}}: serde_flow: decode"))
+            .count(),
+        2
+    );
+}
@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::encoder::{bincode, json};
+use serde_flow::flow::{Bytes, File, FileMigrate};
+use serde_flow::Flow;
+use std::collections::HashMap;
+use tempfile::tempdir;
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 2, file, bytes)]
+#[variants(SettingsV1)]
+pub struct Settings(pub HashMap<String, String>);
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file, bytes)]
+pub struct SettingsV1 {
+    pub theme: String,
+    pub language: String,
+}
+
+impl From<SettingsV1> for Settings {
+    fn from(value: SettingsV1) -> Self {
+        Settings(HashMap::from([
+            ("theme".to_string(), value.theme),
+            ("language".to_string(), value.language),
+        ]))
+    }
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 2, bytes)]
+#[variants(PointV1)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, bytes)]
+pub struct PointV1(pub i32, pub i32);
+
+impl From<PointV1> for Point {
+    fn from(value: PointV1) -> Self {
+        Point {
+            x: value.0,
+            y: value.1,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file, bytes)]
+pub struct Marker;
+
+#[test]
+fn test_newtype_load_and_migrate() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("settings");
+
+    let settings = SettingsV1 {
+        theme: "dark".to_string(),
+        language: "en".to_string(),
+    };
+    settings
+        .save_to_path::<json::Encoder>(path.as_path())
+        .unwrap();
+
+    let settings = Settings::load_and_migrate::<json::Encoder>(path.as_path()).unwrap();
+    assert_eq!(settings.0.get("theme").unwrap().as_str(), "dark");
+
+    let settings = Settings::load_from_path::<json::Encoder>(path.as_path()).unwrap();
+    assert_eq!(settings.0.get("language").unwrap().as_str(), "en");
+}
+
+#[test]
+fn test_tuple_struct_migration() {
+    let bytes = PointV1(3, 4).encode::<bincode::Encoder>().unwrap();
+    let point = Point::decode::<bincode::Encoder>(&bytes).unwrap();
+    assert_eq!(point.x, 3);
+    assert_eq!(point.y, 4);
+
+    let bytes = Point { x: 1, y: 2 }.encode::<bincode::Encoder>().unwrap();
+    assert!(PointV1::decode::<bincode::Encoder>(&bytes).is_err());
+}
+
+#[test]
+fn test_unit_struct_save_and_load() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("marker");
+
+    Marker
+        .save_to_path::<bincode::Encoder>(path.as_path())
+        .unwrap();
+    let marker = Marker::load_from_path::<bincode::Encoder>(path.as_path()).unwrap();
+    assert_eq!(marker, Marker);
+
+    let bytes = Marker.encode::<json::Encoder>().unwrap();
+    assert_eq!(Marker::decode::<json::Encoder>(&bytes).unwrap(), Marker);
+}
+
+mod zerocopy {
+    use rkyv::{Archive, Deserialize, Serialize};
+    use serde_flow::flow::zerocopy::{File, FileMigrate};
+    use serde_flow::Flow;
+    use tempfile::tempdir;
+
+    #[derive(Archive, Serialize, Deserialize, Flow)]
+    #[archive(check_bytes)]
+    #[flow(variant = 2, file, zerocopy)]
+    #[variants(IdV1)]
+    pub struct Id(pub u64, pub String);
+
+    #[derive(Archive, Serialize, Deserialize, Flow)]
+    #[archive(check_bytes)]
+    #[flow(variant = 1, file, zerocopy)]
+    pub struct IdV1 {
+        pub value: u32,
+    }
+
+    impl From<IdV1> for Id {
+        fn from(value: IdV1) -> Self {
+            Id(u64::from(value.value), "default".to_string())
+        }
+    }
+
+    #[test]
+    fn test_tuple_struct_load_and_migrate() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().to_path_buf().join("id");
+
+        IdV1 { value: 9 }.save_to_path(path.as_path()).unwrap();
+
+        let reader = Id::load_and_migrate(path.as_path()).unwrap();
+        let id = reader.archive();
+        assert_eq!(id.0, 9);
+        assert_eq!(id.1, "default");
+    }
+}
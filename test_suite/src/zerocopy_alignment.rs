@@ -0,0 +1,126 @@
+use rkyv::{Archive, Deserialize, Serialize};
+use serde_flow::encoder::zerocopy::{Encoder, Reader};
+use serde_flow::flow::zerocopy::{Bytes, File};
+use serde_flow::Flow;
+use tempfile::tempdir;
+
+/// Four lanes of a SIMD register, archived with its 16-byte alignment.
+#[derive(Debug, Clone, Copy, PartialEq, Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+#[archive_attr(repr(C, align(16)))]
+#[repr(C, align(16))]
+pub struct Lanes {
+    pub values: [f32; 4],
+}
+
+#[derive(Debug, PartialEq, Archive, Serialize, Deserialize, Flow)]
+#[archive(check_bytes)]
+#[flow(variant = 2, file, bytes, zerocopy)]
+#[variants(SampleV1)]
+pub struct Sample {
+    pub tag: u8,
+    pub timestamp: u64,
+    pub id: u128,
+    pub lanes: Lanes,
+}
+
+#[derive(Archive, Serialize, Deserialize, Flow)]
+#[archive(check_bytes)]
+#[flow(variant = 1, file, bytes, zerocopy)]
+pub struct SampleV1 {
+    pub tag: u8,
+    pub id: u128,
+}
+
+impl From<SampleV1> for Sample {
+    fn from(value: SampleV1) -> Self {
+        Sample {
+            tag: value.tag,
+            timestamp: 0,
+            id: value.id,
+            lanes: Lanes { values: [0.0; 4] },
+        }
+    }
+}
+
+fn sample() -> Sample {
+    Sample {
+        tag: 7,
+        timestamp: u64::MAX - 1,
+        id: u128::MAX - 2,
+        lanes: Lanes {
+            values: [1.0, 2.0, 3.0, 4.0],
+        },
+    }
+}
+
+fn assert_aligned(archived: &ArchivedSample) {
+    let address = archived as *const ArchivedSample as usize;
+    assert_eq!(address % std::mem::align_of::<ArchivedSample>(), 0);
+    assert_eq!(std::mem::align_of::<ArchivedSample>(), 16);
+    assert_eq!(archived.timestamp, u64::MAX - 1);
+    assert_eq!(archived.id, u128::MAX - 2);
+    assert_eq!(archived.lanes.values, [1.0, 2.0, 3.0, 4.0]);
+}
+
+#[test]
+fn test_serialize_is_aligned() {
+    let bytes = Encoder::serialize(&sample()).unwrap();
+    assert_eq!(bytes.as_ptr() as usize % 16, 0);
+
+    let reader = Reader::<Sample>::new(bytes).unwrap();
+    assert_aligned(reader.archive());
+}
+
+#[test]
+#[allow(clippy::manual_is_multiple_of)]
+fn test_reader_from_misaligned_slice() {
+    let bytes = Encoder::serialize(&sample()).unwrap();
+    // move the archive to an odd address
+    let mut buffer = vec![0u8; bytes.len() + 1];
+    let offset = if buffer.as_ptr() as usize % 2 == 0 {
+        1
+    } else {
+        0
+    };
+    buffer[offset..offset + bytes.len()].copy_from_slice(&bytes);
+
+    let reader = Reader::<Sample>::from_slice(&buffer[offset..offset + bytes.len()]).unwrap();
+    assert_aligned(reader.archive());
+    assert_eq!(reader.deserialize().unwrap(), sample());
+}
+
+#[test]
+fn test_bytes_round_trip_is_aligned() {
+    let bytes = sample().encode().unwrap();
+    let reader = Sample::decode(bytes).unwrap();
+    assert_aligned(reader.archive());
+}
+
+#[test]
+fn test_bytes_migration_is_aligned() {
+    let bytes = SampleV1 {
+        tag: 3,
+        id: u128::MAX - 2,
+    }
+    .encode()
+    .unwrap();
+    let reader = Sample::decode(bytes).unwrap();
+    let archived = reader.archive();
+    assert_eq!(archived as *const ArchivedSample as usize % 16, 0);
+    assert_eq!(archived.id, u128::MAX - 2);
+}
+
+#[test]
+fn test_file_round_trip_is_aligned() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("sample");
+    sample().save_to_path(path.as_path()).unwrap();
+
+    let reader = Sample::load_from_path(path.as_path()).unwrap();
+    assert_aligned(reader.archive());
+
+    // the archive starts at an aligned offset of the mapped file
+    let reader = Sample::load_from_path_mmap(path.as_path()).unwrap();
+    assert_aligned(&reader);
+}
@@ -27,8 +27,8 @@ fn struct_serialize_archive() {
     };
     let bytes = Encoder::serialize::<User>(&user).unwrap();
 
-    let decoder = Reader::<User>::new(bytes);
-    let user_archived = decoder.archive().unwrap();
+    let decoder = Reader::<User>::new(bytes).unwrap();
+    let user_archived = decoder.archive();
 
     assert_eq!(user_archived.first_name, "John".to_string());
     assert_eq!(user_archived.last_name, "Doe".to_string());
@@ -58,8 +58,8 @@ fn struct_with_hash_map_serialize_archive() {
     );
 
     let bytes = Encoder::serialize::<UsersWithHashMap>(&users).unwrap();
-    let decoder = Reader::<UsersWithHashMap>::new(bytes);
-    let users_archived = decoder.archive().unwrap();
+    let decoder = Reader::<UsersWithHashMap>::new(bytes).unwrap();
+    let users_archived = decoder.archive();
 
     assert_eq!(users_archived.amount, 1234);
 
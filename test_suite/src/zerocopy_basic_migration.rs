@@ -62,7 +62,7 @@ fn test_v2_load_from_path() {
     car_v2.save_to_path(path.as_path()).unwrap();
 
     let car = Car::load_from_path(path.as_path()).unwrap();
-    let car_archived = car.archive().unwrap();
+    let car_archived = car.archive();
 
     assert_eq!(car_archived.name, "BMW x3".to_string());
     assert_eq!(car_archived.price, "$45000".to_string());
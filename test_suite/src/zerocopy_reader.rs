@@ -0,0 +1,105 @@
+use rkyv::{Archive, Deserialize, Serialize};
+use serde_flow::encoder::zerocopy::{Encoder, Reader};
+use serde_flow::error::SerdeFlowError;
+use serde_flow::flow::zerocopy::{Bytes, FileAsync};
+use serde_flow::Flow;
+use std::sync::Arc;
+use tempfile::tempdir;
+
+#[derive(Debug, PartialEq, Archive, Serialize, Deserialize, Flow)]
+#[archive(check_bytes)]
+#[flow(variant = 1, file(nonblocking), bytes, zerocopy)]
+pub struct Route {
+    pub name: String,
+    pub stops: Vec<u64>,
+}
+
+fn route() -> Route {
+    Route {
+        name: "night".to_string(),
+        stops: vec![4, 8, 15, 16, 23, 42],
+    }
+}
+
+fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+#[test]
+fn test_reader_is_send_and_sync() {
+    assert_send_sync::<Reader<Route>>();
+}
+
+#[test]
+fn test_reader_derefs_to_archive() {
+    let reader = Reader::<Route>::new(Encoder::serialize(&route()).unwrap()).unwrap();
+    assert_eq!(reader.name, "night");
+    assert_eq!(reader.stops.as_slice(), &[4, 8, 15, 16, 23, 42]);
+    assert_eq!(reader.deserialize().unwrap(), route());
+}
+
+#[test]
+fn test_reader_from_vec() {
+    let bytes = Encoder::serialize(&route()).unwrap().to_vec();
+    let reader = Reader::<Route>::from_vec(bytes).unwrap();
+    assert_eq!(reader.stops[5], 42);
+}
+
+#[test]
+fn test_reader_from_arc() {
+    let bytes: Arc<[u8]> = Arc::from(Encoder::serialize(&route()).unwrap().as_slice());
+    let reader = Reader::<Route>::from_arc(bytes).unwrap();
+    assert_eq!(reader.name, "night");
+}
+
+#[test]
+fn test_reader_from_bytes() {
+    let archive = Encoder::serialize(&route()).unwrap();
+    let bytes = bytes::Bytes::copy_from_slice(&archive);
+    let reader = Reader::<Route>::from_bytes(bytes).unwrap();
+    assert_eq!(reader.name, "night");
+
+    // a slice of the buffer at an odd offset is copied into an aligned one
+    let mut buffer = bytes::BytesMut::with_capacity(archive.len() + 1);
+    buffer.extend_from_slice(&[0]);
+    buffer.extend_from_slice(&archive);
+    let reader = Reader::<Route>::from_bytes(buffer.freeze().slice(1..)).unwrap();
+    assert_eq!(reader.stops.len(), 6);
+}
+
+#[test]
+fn test_reader_rejects_invalid_archive() {
+    let result = Reader::<Route>::from_vec(vec![1, 2, 3]);
+    assert!(matches!(
+        result,
+        Err(SerdeFlowError::Decode { encoder: 1, .. })
+    ));
+}
+
+#[test]
+fn test_decoded_reader_outlives_bytes() {
+    let reader = {
+        let bytes = route().encode().unwrap();
+        Route::decode(bytes).unwrap()
+    };
+    assert_eq!(reader.name, "night");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_reader_shared_between_tasks() {
+    let temp_dir = tempdir().unwrap();
+    let path = temp_dir.path().to_path_buf().join("route");
+    route().save_to_path_async(path.clone()).await.unwrap();
+
+    let reader = Arc::new(Route::load_from_path_async(path).await.unwrap());
+    let tasks: Vec<_> = (0..4)
+        .map(|index| {
+            let reader = Arc::clone(&reader);
+            tokio::spawn(async move { reader.stops[index] })
+        })
+        .collect();
+
+    let mut stops = Vec::new();
+    for task in tasks {
+        stops.push(task.await.unwrap());
+    }
+    assert_eq!(stops, vec![4, 8, 15, 16]);
+}
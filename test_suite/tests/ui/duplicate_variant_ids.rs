@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::Flow;
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 3, file)]
+#[variants(UserV1, UserV2)]
+pub struct User {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1)]
+pub struct UserV1 {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1)]
+pub struct UserV2 {
+    pub first_name: String,
+}
+
+impl From<UserV1> for User {
+    fn from(value: UserV1) -> Self {
+        User { name: value.name }
+    }
+}
+
+impl From<UserV2> for User {
+    fn from(value: UserV2) -> Self {
+        User {
+            name: value.first_name,
+        }
+    }
+}
+
+fn main() {}
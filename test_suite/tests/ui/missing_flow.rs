@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::Flow;
+
+#[derive(Serialize, Deserialize, Flow)]
+pub struct User {
+    pub name: String,
+}
+
+fn main() {}
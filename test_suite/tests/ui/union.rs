@@ -0,0 +1,10 @@
+use serde_flow::Flow;
+
+#[derive(Flow)]
+#[flow(variant = 1)]
+pub union Number {
+    pub int: u32,
+    pub float: f32,
+}
+
+fn main() {}
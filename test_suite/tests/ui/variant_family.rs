@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::Flow;
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1)]
+pub struct UserV1 {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 2)]
+#[variants(serde(UserV1))]
+pub struct User {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 3)]
+#[variants(rkyv(UserV1))]
+pub struct UserV3 {
+    pub name: String,
+}
+
+fn main() {}
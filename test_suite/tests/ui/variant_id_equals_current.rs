@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::Flow;
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1, file)]
+#[variants(UserV1)]
+pub struct User {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 1)]
+pub struct UserV1 {
+    pub name: String,
+}
+
+impl From<UserV1> for User {
+    fn from(value: UserV1) -> Self {
+        User { name: value.name }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+use serde_flow::Flow;
+
+#[derive(Serialize, Deserialize, Flow)]
+#[flow(variant = 70000, file)]
+pub struct User {
+    pub name: String,
+}
+
+fn main() {}
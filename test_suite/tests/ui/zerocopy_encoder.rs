@@ -0,0 +1,10 @@
+use serde_flow::Flow;
+
+#[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, Flow)]
+#[archive(check_bytes)]
+#[flow(variant = 1, file, zerocopy, encoder = bincode)]
+pub struct Point {
+    pub x: i32,
+}
+
+fn main() {}